@@ -0,0 +1,39 @@
+# Example of pricing a book of callable bonds in one call
+# Bonds share the yield curve and vol, each has its own coupon, maturity, call price and first call
+
+import numpy as np
+
+from rusty_fy import fixed_income, rates
+
+
+curve = rates.YieldCurve([1, 2, 5, 10, 30], [0.030, 0.032, 0.036, 0.040, 0.044])
+n_bonds = 1000
+rng = np.random.default_rng(7)
+coupons = rng.uniform(3.0, 5.0, n_bonds)
+maturities = rng.integers(5, 31, n_bonds)
+first_calls = rng.integers(1, 5, n_bonds).astype(float)
+market_prices = rng.uniform(96.0, 104.0, n_bonds)
+
+prices, spreads, durations, convexities, errors = fixed_income.price_bonds(
+    np.full(n_bonds, 100.0), coupons, maturities, np.full(n_bonds, 100.0), market_prices,
+    curve, 0.15, first_calls=first_calls, calibration="bdt", steps_per_year=4,
+)
+print("Prices:", prices[:5])
+print("OAS (bp):", spreads[:5] * 1e4)
+print("Effective durations:", durations[:5])
+print("Effective convexities:", convexities[:5])
+
+# A bond that fails to price is NaN in every array, with the reason in errors
+failed = [(i, error) for i, error in enumerate(errors) if error is not None]
+print("Failed bonds:", len(failed), failed[:3])
+
+# Same numbers as the bond methods
+i = 0
+bond = fixed_income.OptionEmbeddedBond.from_curve(
+    100, "call", 100, curve, maturities[i], 0.15, [coupons[i]] * maturities[i],
+    calibration="bdt", steps_per_year=4, schedule=[(first_calls[i], 100.0, "american")],
+)
+print()
+print("Bond price:", bond.price, "batch:", prices[i])
+print("Bond OAS:", bond.oas(market_prices[i]), "batch:", spreads[i])
+print("Bond duration:", bond.effective_duration(), "batch:", durations[i])
@@ -0,0 +1,29 @@
+# Example of fitting a curve directly to a basket of coupon bond prices
+# Rich/cheap compares market yields with yields at the fitted model prices
+
+from rusty_fy import fixed_income, rates
+
+
+# Semi-annual bonds priced off a Nelson-Siegel curve, the 7 year bond marked 10 cents rich
+true_curve = rates.NelsonSiegel(0.04, -0.01, 0.005, 2.0)
+terms = [(1, 2.0), (2, 3.0), (3, 3.5), (5, 4.0), (7, 4.2), (10, 4.5), (15, 4.6), (20, 5.0), (30, 5.0)]
+
+bonds = []
+prices = []
+for maturity, coupon in terms:
+    bonds.append(fixed_income.SimpleBond(100, maturity, coupon / 2, 2, 0.04))
+    price = sum(coupon / 2 * true_curve.discount_factor(k / 2) for k in range(1, 2 * maturity + 1))
+    price += 100 * true_curve.discount_factor(maturity)
+    prices.append(price + (0.10 if maturity == 7 else 0.0))
+
+# Duration weighting minimises approximate yield errors
+ns_fit = fixed_income.BondCurveFitter(bonds, prices, model="nelson_siegel", weighting="duration")
+print("Nelson-Siegel params:", ns_fit.curve.params)
+print("Price rmse:", ns_fit.fit.rmse)
+
+# Spline on zero rates at chosen knots
+spline_fit = fixed_income.BondCurveFitter(bonds, prices, model="spline", knots=[1, 3, 5, 10, 20, 30])
+print("Spline zero rates:", spline_fit.curve.zero_rates)
+
+for (maturity, _), bp, view in zip(terms, ns_fit.yield_residuals_bp, ns_fit.rich_cheap):
+    print(f"{maturity:>3}y {bp:+.2f}bp {view}")
@@ -0,0 +1,27 @@
+# Example of bootstrapping a curve from market instruments and
+# pricing a callable bond on the bootstrapped forward curve
+
+from rusty_fy import fixed_income, rates
+
+
+instruments = [
+    rates.CurveInstrument.deposit(0.25, 0.030),
+    rates.CurveInstrument.deposit(0.5, 0.031),
+    rates.CurveInstrument.fra(0.5, 1.0, 0.033),
+    rates.CurveInstrument.future(1.0, 1.25, 96.5, convexity_adjustment=0.0005),
+    rates.CurveInstrument.swap(2, 0.035),
+    rates.CurveInstrument.swap(3, 0.036),
+    rates.CurveInstrument.swap(5, 0.038),
+    rates.CurveInstrument.swap(10, 0.041),
+]
+
+bootstrap = rates.Bootstrapper(instruments, interpolation="cubic", variable="log_discount")
+
+print("Iterations:", bootstrap.iterations)
+print("Repricing errors:", bootstrap.errors)
+for t, r in zip(bootstrap.curve.times, bootstrap.curve.zero_rates):
+    print(t, r)
+
+forward_curve = bootstrap.forward_curve(5)
+bond = fixed_income.OptionEmbeddedBond(100, "call", 100, forward_curve, 0.2, [4] * 5)
+print("Callable bond price:", bond.price)
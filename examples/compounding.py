@@ -0,0 +1,20 @@
+# Example of converting rates between compounding conventions
+# and pricing the same bond under each convention
+
+from rusty_fy import fixed_income, rates
+
+
+annual = rates.Compounding.periodic(1)
+semi_annual = rates.Compounding.periodic(2)
+continuous = rates.Compounding.continuous()
+money_market = rates.Compounding.simple_then_compounded(4)
+
+rate = 0.05
+t = 3.0
+for compounding in [semi_annual, continuous, money_market]:
+    print(compounding, annual.convert(rate, t, compounding))
+
+# Equivalent continuous rate gives the same bond price
+continuous_rate = annual.convert(rate, 5.0, continuous)
+print(fixed_income.SimpleBond(1000, 5, 20, 1, rate).price)
+print(fixed_income.SimpleBond(1000, 5, 20, 1, continuous_rate, continuous).price)
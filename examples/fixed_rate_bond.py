@@ -0,0 +1,29 @@
+# Example of pricing a dated fixed rate bond on a settlement date
+# Yield stated with compounding at the coupon frequency (street convention)
+
+from rusty_fy import fixed_income, rates
+from datetime import date
+
+
+issue_date = date(2023, 11, 15)
+maturity_date = date(2033, 11, 15)
+coupon_rate = 0.045
+frequency = 2
+day_count = "act/act"
+settlement_date = date(2024, 2, 20)
+yield_rate = 0.0425
+
+bond = fixed_income.FixedRateBond(
+    issue_date, maturity_date, coupon_rate, frequency, day_count, settlement_date, yield_rate
+)
+
+print("Accrued interest:", bond.accrued_interest)
+print("Dirty price:", bond.dirty_price)
+print("Clean price:", bond.clean_price)
+
+# Price the same bond on a later settlement date
+print("Clean price on 2024-06-03:", bond.price_from_yield(yield_rate, date(2024, 6, 3)))
+
+# Recover the yield from a quoted clean price
+print("Yield from clean price 101.5:", bond.yield_from_price(101.5))
+print("Annual compounded yield:", bond.yield_from_price(101.5, compounding=rates.Compounding.periodic(1)))
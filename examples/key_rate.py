@@ -0,0 +1,23 @@
+# Example of key rate durations and bucketed PV01 on a yield curve
+# Tent bumps add up to a parallel shift, pillar bumps re-fit the curve through the bumped node
+
+from rusty_fy import fixed_income, rates
+
+
+times = [1, 2, 3, 5, 7, 10, 20, 30]
+zero_rates = [0.030, 0.032, 0.034, 0.036, 0.038, 0.040, 0.042, 0.042]
+curve = rates.YieldCurve(times, zero_rates, interpolation="cubic")
+
+straight = fixed_income.SimpleBond(100, 10, 2.0, 2, 0.04, rates.Compounding.continuous())
+callable_bond = fixed_income.OptionEmbeddedBond.from_curve(100, "call", 100, curve, 10, 0.1, [4.0] * 10)
+
+for method in ["tent", "pillar"]:
+    risk = fixed_income.KeyRateRisk(curve, [straight, callable_bond], method=method)
+    print(method, "key rate durations")
+    for name, durations, total in zip(["straight", "callable"], risk.key_rate_durations, risk.total_durations):
+        print(f"  {name:>8}", [round(d, 3) for d in durations], "total", round(total, 3))
+    print(method, "callable bucketed PV01", [round(p, 4) for p in risk.bucketed_pv01[1]])
+
+# Coarser buckets with tent bumps at chosen key times
+buckets = fixed_income.KeyRateRisk(curve, [straight], key_times=[2, 5, 10, 30])
+print("Buckets", buckets.key_times, [round(d, 3) for d in buckets.key_rate_durations[0]])
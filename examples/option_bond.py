@@ -1,7 +1,10 @@
 # Example of calculating the price of a callable bond
 # All rates stated as continously compounded
 
-from rusty_fy import fixed_income
+import math
+from concurrent.futures import ThreadPoolExecutor
+
+from rusty_fy import fixed_income, interpolate
 
 
 notional = 100
@@ -16,7 +19,7 @@ bond = fixed_income.OptionEmbeddedBond(
     notional, "call", option_price, forward_curve, interest_vol
 )
 
-print("Callable no coupon bond price:", bond.binomial_tree[0].prices[0])
+print("Callable no coupon bond price:", bond.price)
 for level in bond.binomial_tree:
     print(level.prices)
     print(level.rates)
@@ -28,7 +31,8 @@ bond = fixed_income.OptionEmbeddedBond(
 )
 
 print()
-print("Callable coupon bond price:", bond.binomial_tree[0].prices[0])
+print("Callable coupon bond price:", bond.price)
+print("Straight value:", bond.straight_value, "option value:", bond.option_value)
 for level in bond.binomial_tree:
     print(level.prices)
     print(level.rates)
@@ -40,7 +44,7 @@ bond = fixed_income.OptionEmbeddedBond(
 )
 
 print()
-print("Putable bond price:", bond.binomial_tree[0].prices[0])
+print("Putable bond price:", bond.price)
 for level in bond.binomial_tree:
     print(level.prices)
     print(level.rates)
@@ -52,7 +56,155 @@ bond = fixed_income.OptionEmbeddedBond(
 )
 
 print()
-print("Putable coupon bond price:", bond.binomial_tree[0].prices[0])
+print("Putable coupon bond price:", bond.price)
+print("Straight value:", bond.straight_value, "option value:", bond.option_value)
 for level in bond.binomial_tree:
     print(level.prices)
     print(level.rates)
+
+
+# Effective risk measures from parallel forward shifts and a vol bump
+bond = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, forward_curve, interest_vol, coupons
+)
+
+print()
+print("Callable effective duration:", bond.effective_duration())
+print("Callable effective convexity:", bond.effective_convexity())
+print("Callable vega per vol point:", bond.vega())
+
+
+# Option adjusted spread over the tree rates from a market price
+market_price = 99.5
+oas = bond.oas(market_price)
+print()
+print("OAS:", oas)
+print("Price at OAS:", bond.price_with_spread(oas))
+
+
+# Black-Derman-Toy calibration reprices the zero coupon bonds of the forward curve
+for calibration in ["none", "bdt"]:
+    bond = fixed_income.OptionEmbeddedBond(
+        notional, "call", option_price, forward_curve, interest_vol, coupons,
+        calibration=calibration,
+    )
+    print()
+    print(calibration, "price:", bond.price, "calibration errors:", bond.calibration_errors)
+
+
+# Hull-White trinomial tree, interest_vol is a normal vol of the short rate
+bond = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, forward_curve, 0.01, coupons,
+    model="hull_white", mean_reversion=0.05,
+)
+print()
+print("Hull-White callable price:", bond.price, "option value:", bond.option_value)
+for level in bond.trinomial_tree:
+    print(level.rates)
+
+
+# Finer time steps, forwards interpolated onto the grid and coupons paid at their nearest step
+bond = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, forward_curve, interest_vol, coupons,
+    calibration="bdt", steps_per_year=4,
+)
+print()
+print("Quarterly step price:", bond.price, "dt:", bond.dt)
+print("Convergence:", bond.convergence_study([1, 2, 4, 8, 16, 32]))
+
+# Without vol the callable is worth the same on any grid, exercise falls on coupon dates
+flat = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, forward_curve, 0.0, coupons, calibration="bdt",
+)
+print("Zero vol convergence:", flat.convergence_study([1, 2, 4, 12]))
+
+
+# Call schedule with declining premiums after a lockout, bermudan dates and a european call
+long_forwards = [0.02, 0.025, 0.03, 0.035, 0.04, 0.04, 0.042, 0.045, 0.045, 0.046]
+long_coupons = [4.0] * 10
+schedule = [(3.0, 102.0, "american"), (5.0, 101.0, "american"), (7.0, 100.0, "american")]
+scheduled = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, long_forwards, 0.15, long_coupons, schedule=schedule
+)
+bermudan = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, long_forwards, 0.15, long_coupons,
+    schedule=[(t, 100.0, "bermudan") for t in [3.0, 5.0, 7.0]],
+)
+european = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, long_forwards, 0.15, long_coupons,
+    schedule=[(5.0, 100.0, "american")], european=True,
+)
+print()
+print("Strikes by step:", scheduled.call_strikes)
+print("Scheduled call price:", scheduled.price)
+print("Bermudan call price:", bermudan.price)
+print("European call price:", european.price)
+
+# Schedule prices are clean, a call between coupon dates also pays the accrued coupon
+mid_year = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, forward_curve, 0.0, [6, 6, 6],
+    steps_per_year=4, schedule=[(2.5, 100.0, "american")],
+)
+df = [math.exp(-sum(mid_year.step_forwards[:n]) * mid_year.dt) for n in (4, 8, 10)]
+print("Called mid year:", mid_year.price, "expected:", 6 * df[0] + 6 * df[1] + (100 + 3) * df[2])
+
+
+# Callable by the issuer and putable by the holder, each side on its own schedule
+call_put = fixed_income.OptionEmbeddedBond(
+    notional, "call_put", option_price, long_forwards, 0.15, long_coupons,
+    schedule=schedule, put_price=95.0, put_schedule=[(4.0, 97.0, "bermudan"), (6.0, 98.0, "bermudan")],
+)
+print()
+print("Put strikes by step:", call_put.put_strikes)
+print("Callable and putable price:", call_put.price, "option value:", call_put.option_value)
+for step in (4, 5):
+    print("Step", step, "called:", call_put.call_map[step], "put:", call_put.put_map[step])
+
+
+# Exercise boundary, probability of call on each step and expected life
+print()
+print("Exercised nodes on step 3:", scheduled.exercise_map[3])
+print("Call probability by step:", scheduled.exercise_probabilities)
+print("Probability of call:", sum(scheduled.exercise_probabilities))
+print("Expected life:", scheduled.expected_life)
+
+
+# Humped vol term structure, BDT still reprices the curve zero coupon bonds
+humped = interpolate.LinearSpline([0.0, 2.0, 10.0], [0.12, 0.20, 0.14])
+humped_bond = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, long_forwards, 0.15, long_coupons,
+    calibration="bdt", steps_per_year=2, vol_curve=humped,
+)
+print()
+print("Vol curve:", humped_bond.vol_curve, "step vols:", humped_bond.step_vols)
+print("Humped vol price:", humped_bond.price, "vega:", humped_bond.vega())
+print("Largest zero coupon error:", max(abs(e) for e in humped_bond.calibration_errors))
+
+
+# Rollback over two levels of values, keeping no tree but the rates and values of chosen steps
+lean = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, long_forwards, 0.15, long_coupons,
+    calibration="bdt", steps_per_year=12, keep_tree=False, keep_levels=[0, 60],
+)
+full = fixed_income.OptionEmbeddedBond(
+    notional, "call", option_price, long_forwards, 0.15, long_coupons,
+    calibration="bdt", steps_per_year=12,
+)
+step, rates, values = lean.retained_levels[1]
+print()
+print("Price without the tree:", lean.price, "with the tree:", full.price)
+print("Levels kept:", len(lean.binomial_tree), "step", step, "nodes:", len(rates))
+print("Median value on step", step, ":", values[len(values) // 2])
+
+
+# The GIL is released while valuing so bonds can be priced from several threads
+def price_at_vol(vol):
+    return fixed_income.OptionEmbeddedBond(
+        notional, "call", option_price, long_forwards, vol, long_coupons,
+        calibration="bdt", steps_per_year=12, keep_tree=False,
+    ).price
+
+
+with ThreadPoolExecutor(max_workers=4) as pool:
+    prices = list(pool.map(price_at_vol, [0.10, 0.15, 0.20, 0.25]))
+print("Prices by vol:", prices)
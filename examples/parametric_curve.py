@@ -0,0 +1,29 @@
+# Example of fitting Nelson-Siegel and Svensson curves to zero yields
+# Yields stated as continously compounded
+
+from rusty_fy import rates
+import matplotlib.pyplot as plt
+import numpy as np
+
+
+times = [0.25, 0.5, 1, 2, 3, 5, 7, 10, 20, 30]
+yields = [0.0300, 0.0305, 0.0310, 0.0330, 0.0345, 0.0370, 0.0385, 0.0400, 0.0420, 0.0415]
+
+ns = rates.NelsonSiegel.fit_yields(times, yields)
+sv = rates.Svensson.fit_yields(times, yields)
+
+for name, curve in [("Nelson-Siegel", ns), ("Svensson", sv)]:
+    print(name, "params:", curve.params)
+    print(name, "rmse:", curve.fit.rmse, "converged:", curve.fit.converged)
+
+# Hold the decay parameter between 1 and 3 years
+bounded = rates.NelsonSiegel.fit_yields(times, yields, lower=[-0.5, -1, -1, 1.0], upper=[0.5, 1, 1, 3.0])
+print("Bounded Nelson-Siegel params:", bounded.params)
+
+t = np.linspace(0.1, 30, 200)
+plt.scatter(times, yields, label="market")
+plt.plot(t, [ns.zero_rate(x) for x in t], label="nelson-siegel")
+plt.plot(t, [sv.zero_rate(x) for x in t], label="svensson")
+plt.plot(t, [sv.instantaneous_forward(x) for x in t], label="svensson forward")
+plt.legend()
+plt.show()
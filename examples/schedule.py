@@ -0,0 +1,23 @@
+# Example of generating a semi-annual coupon schedule with a short front stub
+
+from rusty_fy import dates
+from datetime import date
+
+
+holidays = [date(2024, 12, 25), date(2025, 1, 1), date(2025, 12, 25), date(2026, 1, 1)]
+calendar = dates.Calendar(holidays)
+
+schedule = dates.Schedule(
+    date(2024, 1, 31),
+    date(2026, 11, 30),
+    2,
+    calendar,
+    adjustment="modified_following",
+    stub="short_front",
+    end_of_month=True,
+)
+
+for start, end, pay in zip(
+    schedule.accrual_start_dates, schedule.accrual_end_dates, schedule.payment_dates
+):
+    print(start, end, pay)
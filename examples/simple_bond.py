@@ -17,6 +17,14 @@ print("Current price:", bond.price)
 print("Modified duration:", bond.mod_duration)
 print("Macaulay duration:", bond.mac_duration)
 print("Convexity:", bond.convexity)
+print("DV01:", bond.dv01)
+
+# Compare the analytics with finite difference bumps of the price
+h = 0.0001
+up = fixed_income.SimpleBond(notional, n_periods, coupon_amount, coupon_freq, interest_rate + h)
+down = fixed_income.SimpleBond(notional, n_periods, coupon_amount, coupon_freq, interest_rate - h)
+print("Bumped modified duration:", (down.price - up.price) / (2 * h * bond.price))
+print("Bumped convexity:", (up.price + down.price - 2 * bond.price) / (h**2 * bond.price))
 
 plt.xlabel("Interest Rate")
 plt.ylabel("Price")
@@ -0,0 +1,41 @@
+# Example of calibrating the BDT tree vol to cap, floor and swaption quotes
+# Quotes are (kind, expiry, tenor, strike, quote), prices per unit notional or Black vols
+
+from rusty_fy import fixed_income
+
+
+forward_curve = [0.02, 0.025, 0.03, 0.035, 0.04, 0.04, 0.042, 0.045, 0.045, 0.046]
+quotes = [
+    ("cap", 1, 2, 0.030, 0.22),
+    ("cap", 1, 4, 0.035, 0.21),
+    ("floor", 1, 6, 0.030, 0.19),
+    ("payer", 2, 3, 0.040, 0.20),
+    ("receiver", 5, 5, 0.040, 0.18),
+    ("payer", 7, 3, 0.045, 0.17),
+]
+
+flat = fixed_income.TreeVolCalibration(forward_curve, quotes, quote_type="black_vol", steps_per_year=4)
+print("Flat vol:", flat.vols[0], "rmse:", flat.fit.rmse)
+print("Vol errors bp:", [round(e, 2) for e in flat.vol_errors_bp])
+
+term = fixed_income.TreeVolCalibration(
+    forward_curve, quotes, quote_type="black_vol", vol_model="term_structure", steps_per_year=4
+)
+print()
+print("Knot times:", term.knot_times)
+print("Term structure vols:", [round(v, 4) for v in term.vols])
+print("Price errors:", term.price_errors)
+print("Vol errors bp:", [round(e, 2) for e in term.vol_errors_bp])
+
+# Price quotes work the same way, the fitted step vols plug into the bond on the same grid
+priced = fixed_income.TreeVolCalibration(
+    forward_curve, [q[:4] + (p,) for q, p in zip(quotes, term.model_prices)],
+    vol_model="term_structure", steps_per_year=4,
+)
+print()
+print("Recovered vols:", [round(v, 4) for v in priced.vols], "converged:", priced.fit.converged)
+bond = fixed_income.OptionEmbeddedBond(
+    100, "call", 100, forward_curve, 0.2, [4.0] * 10,
+    calibration="bdt", steps_per_year=4, vol_curve=priced.step_vols,
+)
+print("Callable price on calibrated vols:", bond.price)
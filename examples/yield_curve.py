@@ -0,0 +1,35 @@
+# Example of building a yield curve and pricing bonds off it
+# Pillar rates stated as continously compounded unless a compounding is given
+
+from datetime import date
+
+from rusty_fy import fixed_income, rates
+import matplotlib.pyplot as plt
+import numpy as np
+
+
+times = [0.5, 1, 2, 3, 5, 7, 10]
+zero_rates = [0.030, 0.032, 0.035, 0.037, 0.040, 0.041, 0.042]
+
+curve = rates.YieldCurve(times, zero_rates, interpolation="cubic", variable="log_discount")
+
+print("5y discount factor:", curve.discount_factor(5.0))
+print("5y annual zero rate:", curve.zero_rate(5.0, rates.Compounding.periodic(1)))
+print("2y3y forward rate:", curve.forward_rate(2.0, 5.0))
+
+# Dates are measured from the reference date, act/act splits the span by calendar year
+dated = rates.YieldCurve(times, zero_rates, reference_date=date(2024, 7, 1), day_count="act/act")
+print("Discount factor to 2026-01-01:", dated.discount_factor(date(2026, 1, 1)))
+
+# Price instruments on the curve
+bond = fixed_income.SimpleBond(1000, 5, 40, 2, 0.04)
+print("Simple bond on curve:", bond.price_from_curve(curve))
+
+callable_bond = fixed_income.OptionEmbeddedBond.from_curve(100, "call", 100, curve, 5, 0.2)
+print("Callable bond on curve:", callable_bond.price)
+
+t = np.linspace(0.1, 10, 100)
+plt.plot(t, [curve.zero_rate(x) for x in t], label="zero")
+plt.plot(t, [curve.instantaneous_forward(x) for x in t], label="forward")
+plt.legend()
+plt.show()
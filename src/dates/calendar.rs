@@ -0,0 +1,164 @@
+/*
+Business day calendar and date adjustment rules
+- Weekends (Saturday, Sunday) are non business days unless disabled
+- Holidays are supplied as a list of dates
+*/
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BusinessDayConvention {
+    Unadjusted,
+    Following,
+    ModifiedFollowing,
+    Preceding,
+    ModifiedPreceding,
+}
+
+impl BusinessDayConvention {
+    pub fn parse(rule: &str) -> PyResult<Self> {
+        match rule {
+            "unadjusted" => Ok(BusinessDayConvention::Unadjusted),
+            "following" => Ok(BusinessDayConvention::Following),
+            "modified_following" => Ok(BusinessDayConvention::ModifiedFollowing),
+            "preceding" => Ok(BusinessDayConvention::Preceding),
+            "modified_preceding" => Ok(BusinessDayConvention::ModifiedPreceding),
+            _ => Err(PyValueError::new_err(
+                "Invalid adjustment rule. Use unadjusted, following, modified_following, preceding or modified_preceding",
+            )),
+        }
+    }
+}
+
+
+#[derive(Clone)]
+#[pyclass]
+pub struct Calendar {
+    #[pyo3(get)]
+    holidays: Vec<NaiveDate>,
+    #[pyo3(get)]
+    weekends: bool,
+}
+
+#[pymethods]
+impl Calendar {
+    #[new]
+    #[pyo3(signature = (holidays=None, weekends=true))]
+    pub fn new(holidays: Option<Vec<NaiveDate>>, weekends: bool) -> Self {
+        let mut holidays = holidays.unwrap_or_default();
+        holidays.sort();
+        holidays.dedup();
+
+        Calendar { holidays, weekends }
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        if self.weekends && matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            return false;
+        }
+        self.holidays.binary_search(&date).is_err()
+    }
+
+    // Move a date onto a business day using the given rule
+    #[pyo3(name = "adjust")]
+    fn py_adjust(&self, date: NaiveDate, rule: &str) -> PyResult<NaiveDate> {
+        Ok(self.adjust(date, BusinessDayConvention::parse(rule)?))
+    }
+
+    // Move a date forward (or backward when negative) by n business days
+    pub fn add_business_days(&self, date: NaiveDate, n: i64) -> NaiveDate {
+        let mut date = date;
+        let mut remaining = n.abs();
+        while remaining > 0 {
+            date = if n > 0 { next_day(date) } else { previous_day(date) };
+            if self.is_business_day(date) {
+                remaining -= 1;
+            }
+        }
+
+        date
+    }
+}
+
+impl Calendar {
+    pub fn adjust(&self, date: NaiveDate, rule: BusinessDayConvention) -> NaiveDate {
+        match rule {
+            BusinessDayConvention::Unadjusted => date,
+            BusinessDayConvention::Following => self.following(date),
+            BusinessDayConvention::Preceding => self.preceding(date),
+            BusinessDayConvention::ModifiedFollowing => {
+                let adjusted = self.following(date);
+                if adjusted.month() != date.month() {
+                    self.preceding(date)
+                } else {
+                    adjusted
+                }
+            }
+            BusinessDayConvention::ModifiedPreceding => {
+                let adjusted = self.preceding(date);
+                if adjusted.month() != date.month() {
+                    self.following(date)
+                } else {
+                    adjusted
+                }
+            }
+        }
+    }
+
+    fn following(&self, date: NaiveDate) -> NaiveDate {
+        let mut date = date;
+        while !self.is_business_day(date) {
+            date = next_day(date);
+        }
+        date
+    }
+
+    fn preceding(&self, date: NaiveDate) -> NaiveDate {
+        let mut date = date;
+        while !self.is_business_day(date) {
+            date = previous_day(date);
+        }
+        date
+    }
+}
+
+
+fn next_day(date: NaiveDate) -> NaiveDate {
+    date.checked_add_days(Days::new(1)).unwrap()
+}
+
+fn previous_day(date: NaiveDate) -> NaiveDate {
+    date.checked_sub_days(Days::new(1)).unwrap()
+}
+
+// Last calendar day of the month containing date
+pub fn end_of_month(date: NaiveDate) -> NaiveDate {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    previous_day(NaiveDate::from_ymd_opt(year, month, 1).unwrap())
+}
+
+pub fn is_end_of_month(date: NaiveDate) -> bool {
+    date == end_of_month(date)
+}
+
+// Shift a date by a number of months, keeping month end dates on month end when eom is set
+pub fn add_months(date: NaiveDate, months: i32, eom: bool) -> NaiveDate {
+    let shifted = if months >= 0 {
+        date.checked_add_months(Months::new(months as u32)).unwrap()
+    } else {
+        date.checked_sub_months(Months::new(months.unsigned_abs())).unwrap()
+    };
+
+    if eom && is_end_of_month(date) {
+        end_of_month(shifted)
+    } else {
+        shifted
+    }
+}
@@ -0,0 +1,102 @@
+/*
+Day count conventions used to measure accrual periods
+- act/act follows the ICMA rule, the period fraction is measured against the coupon period
+- Without a coupon period, act/act times split the span by calendar year, days in each year
+  over 365 or 366
+*/
+
+use chrono::{Datelike, NaiveDate};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DayCount {
+    Thirty360,
+    ThirtyE360,
+    Act360,
+    Act365,
+    ActActIcma,
+}
+
+impl DayCount {
+    pub fn parse(day_count: &str) -> PyResult<Self> {
+        match day_count {
+            "30/360" => Ok(DayCount::Thirty360),
+            "30e/360" => Ok(DayCount::ThirtyE360),
+            "act/360" => Ok(DayCount::Act360),
+            "act/365" => Ok(DayCount::Act365),
+            "act/act" => Ok(DayCount::ActActIcma),
+            _ => Err(PyValueError::new_err(
+                "Invalid day count. Use 30/360, 30e/360, act/360, act/365 or act/act",
+            )),
+        }
+    }
+
+    // Number of days between two dates under the convention
+    pub fn days(&self, start: NaiveDate, end: NaiveDate) -> f64 {
+        match self {
+            DayCount::Thirty360 => {
+                let d1 = if start.day() == 31 { 30 } else { start.day() };
+                let d2 = if end.day() == 31 && d1 >= 30 { 30 } else { end.day() };
+                thirty_360_days(start, end, d1, d2)
+            }
+            DayCount::ThirtyE360 => {
+                let d1 = start.day().min(30);
+                let d2 = end.day().min(30);
+                thirty_360_days(start, end, d1, d2)
+            }
+            _ => (end - start).num_days() as f64,
+        }
+    }
+
+    // Year fraction between two dates, act/act needs the coupon period dates and frequency
+    pub fn year_fraction(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        ref_start: NaiveDate,
+        ref_end: NaiveDate,
+        frequency: u32,
+    ) -> f64 {
+        match self {
+            DayCount::Thirty360 | DayCount::ThirtyE360 => self.days(start, end) / 360.0,
+            DayCount::Act360 => self.days(start, end) / 360.0,
+            DayCount::Act365 => self.days(start, end) / 365.0,
+            DayCount::ActActIcma => {
+                self.days(start, end) / (self.days(ref_start, ref_end) * frequency as f64)
+            }
+        }
+    }
+
+    // Time in years between two dates with no coupon period, negative if end is before start
+    pub fn year_time(&self, start: NaiveDate, end: NaiveDate) -> f64 {
+        match self {
+            DayCount::ActActIcma => {
+                if end < start {
+                    return -self.year_time(end, start);
+                }
+                (start.year()..=end.year())
+                    .map(|year| {
+                        let from = start.max(year_start(year));
+                        let to = end.min(year_start(year + 1));
+                        (to - from).num_days() as f64 / (year_start(year + 1) - year_start(year)).num_days() as f64
+                    })
+                    .sum()
+            }
+            _ => self.year_fraction(start, end, start, end, 1),
+        }
+    }
+}
+
+
+fn year_start(year: i32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, 1, 1).unwrap()
+}
+
+
+fn thirty_360_days(start: NaiveDate, end: NaiveDate, d1: u32, d2: u32) -> f64 {
+    360.0 * (end.year() - start.year()) as f64
+        + 30.0 * (end.month() as f64 - start.month() as f64)
+        + (d2 as f64 - d1 as f64)
+}
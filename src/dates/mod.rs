@@ -0,0 +1,18 @@
+// Dates Module
+
+use pyo3::prelude::*;
+
+pub mod calendar;
+pub mod day_count;
+pub mod schedule;
+
+
+#[pymodule]
+pub fn register_dates(py: Python, parent_m: &PyModule) -> PyResult<()> {
+    let dates = PyModule::new(py, "dates")?;
+    dates.add_class::<calendar::Calendar>()?;
+    dates.add_class::<schedule::Schedule>()?;
+    parent_m.add_submodule(dates)?;
+
+    Ok(())
+}
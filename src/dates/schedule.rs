@@ -0,0 +1,227 @@
+/*
+Generates a coupon schedule between an effective date and maturity
+- Dates are rolled from maturity for front stubs and from the effective date for back stubs
+- Accrual and payment dates are adjusted onto business days with the calendar
+- The end of month rule keeps rolled dates on month end when the anchor date is a month end
+*/
+
+use chrono::NaiveDate;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::dates::calendar::{add_months, BusinessDayConvention, Calendar};
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StubType {
+    None,
+    ShortFront,
+    LongFront,
+    ShortBack,
+    LongBack,
+}
+
+impl StubType {
+    pub fn parse(stub: &str) -> PyResult<Self> {
+        match stub {
+            "none" => Ok(StubType::None),
+            "short_front" => Ok(StubType::ShortFront),
+            "long_front" => Ok(StubType::LongFront),
+            "short_back" => Ok(StubType::ShortBack),
+            "long_back" => Ok(StubType::LongBack),
+            _ => Err(PyValueError::new_err(
+                "Invalid stub type. Use none, short_front, long_front, short_back or long_back",
+            )),
+        }
+    }
+
+    fn is_front(&self) -> bool {
+        matches!(self, StubType::None | StubType::ShortFront | StubType::LongFront)
+    }
+}
+
+
+#[derive(Clone)]
+#[pyclass]
+pub struct Schedule {
+    #[pyo3(get)]
+    effective_date: NaiveDate,
+    #[pyo3(get)]
+    maturity_date: NaiveDate,
+    #[pyo3(get)]
+    frequency: u32,
+    #[pyo3(get)]
+    calendar: Calendar,
+    #[pyo3(get)]
+    adjustment: String,
+    #[pyo3(get)]
+    stub: String,
+    #[pyo3(get)]
+    end_of_month: bool,
+    #[pyo3(get)]
+    unadjusted_dates: Vec<NaiveDate>,
+    #[pyo3(get)]
+    accrual_start_dates: Vec<NaiveDate>,
+    #[pyo3(get)]
+    accrual_end_dates: Vec<NaiveDate>,
+    #[pyo3(get)]
+    payment_dates: Vec<NaiveDate>,
+}
+
+#[pymethods]
+impl Schedule {
+    #[new]
+    #[pyo3(signature = (
+        effective_date,
+        maturity_date,
+        frequency,
+        calendar=None,
+        adjustment="unadjusted",
+        stub="short_front",
+        end_of_month=false,
+    ))]
+    pub fn new(
+        effective_date: NaiveDate,
+        maturity_date: NaiveDate,
+        frequency: u32,
+        calendar: Option<Calendar>,
+        adjustment: &str,
+        stub: &str,
+        end_of_month: bool,
+    ) -> Result<Self, PyErr> {
+        if maturity_date <= effective_date {
+            return Err(PyValueError::new_err(
+                "Maturity date must be after the effective date",
+            ));
+        }
+        if frequency == 0 || 12 % frequency != 0 {
+            return Err(PyValueError::new_err(
+                "Frequency must be one of 1, 2, 3, 4, 6 or 12 payments per year",
+            ));
+        }
+
+        let mut schedule = Schedule {
+            effective_date,
+            maturity_date,
+            frequency,
+            calendar: calendar.unwrap_or_else(|| Calendar::new(None, true)),
+            adjustment: adjustment.to_string(),
+            stub: stub.to_string(),
+            end_of_month,
+            unadjusted_dates: Vec::new(),
+            accrual_start_dates: Vec::new(),
+            accrual_end_dates: Vec::new(),
+            payment_dates: Vec::new(),
+        };
+        schedule.generate()?;
+
+        Ok(schedule)
+    }
+
+    fn __len__(&self) -> usize {
+        self.payment_dates.len()
+    }
+}
+
+impl Schedule {
+    // Roll the unadjusted dates and derive the adjusted accrual and payment dates
+    fn generate(&mut self) -> Result<(), PyErr> {
+        let rule = BusinessDayConvention::parse(&self.adjustment)?;
+        let stub = StubType::parse(&self.stub)?;
+
+        let dates = if stub.is_front() {
+            self.roll_backward(stub)?
+        } else {
+            self.roll_forward(stub)
+        };
+
+        let adjusted: Vec<NaiveDate> = dates
+            .iter()
+            .map(|date| self.calendar.adjust(*date, rule))
+            .collect();
+
+        self.accrual_start_dates = adjusted[..adjusted.len() - 1].to_vec();
+        self.accrual_end_dates = adjusted[1..].to_vec();
+        self.payment_dates = adjusted[1..].to_vec();
+        self.unadjusted_dates = dates;
+
+        Ok(())
+    }
+
+    pub fn months_per_period(&self) -> i32 {
+        12 / self.frequency as i32
+    }
+
+    // Dates from maturity back to the effective date, stub at the front
+    fn roll_backward(&self, stub: StubType) -> Result<Vec<NaiveDate>, PyErr> {
+        let step = self.months_per_period();
+        let mut dates = vec![self.maturity_date];
+        let mut k = 1;
+        loop {
+            let date = add_months(self.maturity_date, -k * step, self.end_of_month);
+            if date <= self.effective_date {
+                if date < self.effective_date {
+                    match stub {
+                        StubType::None => {
+                            return Err(PyValueError::new_err(
+                                "Dates do not divide into whole periods, a stub type is required",
+                            ))
+                        }
+                        StubType::LongFront if dates.len() > 1 => {
+                            dates.pop();
+                        }
+                        _ => {}
+                    }
+                }
+                dates.push(self.effective_date);
+                break;
+            }
+            dates.push(date);
+            k += 1;
+        }
+        dates.reverse();
+
+        Ok(dates)
+    }
+
+    // Dates from the effective date forward to maturity, stub at the back
+    fn roll_forward(&self, stub: StubType) -> Vec<NaiveDate> {
+        let step = self.months_per_period();
+        let mut dates = vec![self.effective_date];
+        let mut k = 1;
+        loop {
+            let date = add_months(self.effective_date, k * step, self.end_of_month);
+            if date >= self.maturity_date {
+                if date > self.maturity_date && stub == StubType::LongBack && dates.len() > 1 {
+                    dates.pop();
+                }
+                dates.push(self.maturity_date);
+                break;
+            }
+            dates.push(date);
+            k += 1;
+        }
+
+        dates
+    }
+
+    pub fn unadjusted(&self) -> &[NaiveDate] {
+        &self.unadjusted_dates
+    }
+
+    pub fn payments(&self) -> &[NaiveDate] {
+        &self.payment_dates
+    }
+
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    pub fn end_of_month(&self) -> bool {
+        self.end_of_month
+    }
+
+    pub fn has_front_stub(&self) -> bool {
+        self.stub != "short_back" && self.stub != "long_back"
+    }
+}
@@ -0,0 +1,191 @@
+/*
+Prices many option embedded bonds at once on a shared yield curve and vol
+- The curve gives one year forwards out to the longest maturity, as OptionEmbeddedBond.from_curve
+- Bond i pays coupons[i] a year for maturities[i] years on the first maturities[i] forwards
+- Each bond is callable, or putable, at option_prices[i] from first_calls[i] years, or on
+  each coupon date without first calls; call_put bonds are not supported
+- Bonds are valued in parallel with the GIL released, keeping no tree
+- Returns NumPy arrays of price, OAS to market_prices, effective duration and convexity,
+  matching the OptionEmbeddedBond methods of the same names
+- A bond that fails to price is NaN in every array and its error message is returned in
+  the error list, None for the bonds that priced
+*/
+
+use numpy::{IntoPyArray, PyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::fixed_income::option_embedded_bond::OptionEmbeddedBond;
+use crate::rates::compounding::Compounding;
+use crate::rates::yield_curve::YieldCurve;
+
+
+type BatchArrays<'py> = (
+    &'py PyArray1<f64>,
+    &'py PyArray1<f64>,
+    &'py PyArray1<f64>,
+    &'py PyArray1<f64>,
+    Vec<Option<String>>,
+);
+
+
+#[pyfunction]
+#[pyo3(signature = (
+    notionals,
+    coupons,
+    maturities,
+    option_prices,
+    market_prices,
+    curve,
+    interest_vol,
+    bond_option="call",
+    first_calls=None,
+    compounding=None,
+    calibration="none",
+    model="binomial",
+    mean_reversion=0.1,
+    steps_per_year=1,
+    interpolation="linear",
+    bump=0.0001,
+    tol=1e-10,
+    max_iter=100,
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn price_bonds<'py>(
+    py: Python<'py>,
+    notionals: Vec<f64>,
+    coupons: Vec<f64>,
+    maturities: Vec<usize>,
+    option_prices: Vec<f64>,
+    market_prices: Vec<f64>,
+    curve: &YieldCurve,
+    interest_vol: f64,
+    bond_option: &str,
+    first_calls: Option<Vec<f64>>,
+    compounding: Option<Compounding>,
+    calibration: &str,
+    model: &str,
+    mean_reversion: f64,
+    steps_per_year: usize,
+    interpolation: &str,
+    bump: f64,
+    tol: f64,
+    max_iter: usize,
+) -> PyResult<BatchArrays<'py>> {
+    match bond_option {
+        "call" | "put" => {}
+        "call_put" => return Err(PyValueError::new_err(
+            "Batch pricing takes call or put bonds, price call_put bonds with OptionEmbeddedBond",
+        )),
+        _ => {
+            return Err(PyValueError::new_err(
+                "Invalid option type parameter. Use call or put",
+            ))
+        }
+    }
+    let n_bonds = notionals.len();
+    let lengths = [
+        coupons.len(),
+        maturities.len(),
+        option_prices.len(),
+        market_prices.len(),
+    ];
+    if lengths.iter().any(|length| *length != n_bonds)
+        || first_calls
+            .as_ref()
+            .is_some_and(|calls| calls.len() != n_bonds)
+    {
+        return Err(PyValueError::new_err(
+            "Bond term arrays must have the same length",
+        ));
+    }
+    if maturities.contains(&0) {
+        return Err(PyValueError::new_err("Maturities must be positive"));
+    }
+    if market_prices.iter().any(|price| *price <= 0.0) {
+        return Err(PyValueError::new_err("Market prices must be positive"));
+    }
+    if first_calls
+        .as_ref()
+        .is_some_and(|calls| calls.iter().any(|t| *t <= 0.0))
+    {
+        return Err(PyValueError::new_err("Exercise times must be positive"));
+    }
+    let n_years = maturities.iter().max().copied().unwrap_or(0);
+    let forward_curve = curve.forward_curve(n_years, 1.0, compounding)?;
+
+    // Each bond is built, valued and bumped on its own thread
+    let measures = py.allow_threads(|| {
+        (0..n_bonds)
+            .into_par_iter()
+            .map(|i| -> PyResult<(f64, f64, f64, f64)> {
+                let schedule = match &first_calls {
+                    Some(calls) => vec![(calls[i], option_prices[i], "american".to_string())],
+                    None => Vec::new(),
+                };
+                let bond = OptionEmbeddedBond::with_terms(
+                    notionals[i],
+                    bond_option.to_string(),
+                    option_prices[i],
+                    forward_curve[..maturities[i]].to_vec(),
+                    interest_vol,
+                    Some(vec![coupons[i]; maturities[i]]),
+                    compounding,
+                    calibration,
+                    model,
+                    mean_reversion,
+                    steps_per_year,
+                    interpolation,
+                    schedule,
+                    false,
+                    None,
+                    Vec::new(),
+                    None,
+                    false,
+                    Vec::new(),
+                )?;
+                let oas = bond.solve_oas(market_prices[i], tol, max_iter)?;
+                let (down, price, up) = bond.shifted_prices(bump)?;
+                let duration = (down - up) / (2.0 * bump * price);
+                let convexity = (down + up - 2.0 * price) / (bump * bump * price);
+
+                Ok((price, oas, duration, convexity))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // Failed bonds keep their place as NaN with the reason alongside
+    let mut columns = [
+        Vec::with_capacity(n_bonds),
+        Vec::with_capacity(n_bonds),
+        Vec::with_capacity(n_bonds),
+        Vec::with_capacity(n_bonds),
+    ];
+    let mut errors = Vec::with_capacity(n_bonds);
+    for result in measures {
+        let (price, oas, duration, convexity) = match result {
+            Ok(values) => {
+                errors.push(None);
+                values
+            }
+            Err(err) => {
+                errors.push(Some(err.value(py).to_string()));
+                (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+            }
+        };
+        columns[0].push(price);
+        columns[1].push(oas);
+        columns[2].push(duration);
+        columns[3].push(convexity);
+    }
+    let [prices, spreads, durations, convexities] = columns;
+
+    Ok((
+        prices.into_pyarray(py),
+        spreads.into_pyarray(py),
+        durations.into_pyarray(py),
+        convexities.into_pyarray(py),
+        errors,
+    ))
+}
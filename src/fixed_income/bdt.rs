@@ -0,0 +1,141 @@
+/*
+Black-Derman-Toy binomial tree
+- Node i of level n sits at median exp(spacing (n - 2i)) with spacing the step vol times sqrt(dt)
+- Up and down moves are equally likely
+- Unfitted levels take their step forward as the median, fitted levels solve each median from
+  Arrow-Debreu state prices so the tree reprices the zero coupon bonds of the forwards
+- Levels only hold their median and spacing, rates are rebuilt per node
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::fixed_income::lattice::{Branches, Lattice, Level};
+use crate::rates::compounding::Compounding;
+use crate::solver::root;
+
+
+#[derive(Clone)]
+#[pyclass]
+pub struct BinomialTreeLevel {
+    #[pyo3(get)]
+    pub prices: Vec<f64>,
+    #[pyo3(get)]
+    pub rates: Vec<f64>,
+    #[pyo3(get)]
+    pub coupon: f64,
+}
+
+
+// Median rate and log spacing of one level
+#[derive(Clone, Copy, Debug)]
+pub struct BdtLevel {
+    index: usize,
+    median: f64,
+    spacing: f64,
+}
+
+impl BdtLevel {
+    // Full level with its node values, as kept on the bond
+    pub fn expand(&self, coupon: f64, prices: Vec<f64>) -> BinomialTreeLevel {
+        BinomialTreeLevel {
+            prices,
+            rates: self.rates(),
+            coupon,
+        }
+    }
+}
+
+
+// Levels with each median at its one period forward
+pub fn levels(forward_curve: &[f64], step_vols: &[f64], dt: f64) -> PyResult<Vec<BdtLevel>> {
+    if step_vols.len() != forward_curve.len() {
+        return Err(PyValueError::new_err(
+            "Step vols must have one vol per forward",
+        ));
+    }
+
+    Ok(forward_curve
+        .iter()
+        .zip(step_vols.iter())
+        .enumerate()
+        .map(|(index, (forward, vol))| BdtLevel {
+            index,
+            median: *forward,
+            spacing: vol * dt.sqrt(),
+        })
+        .collect())
+}
+
+
+// Build the levels on one period forwards and fit each median to the curve
+pub fn build_levels(
+    forward_curve: &[f64],
+    step_vols: &[f64],
+    dt: f64,
+    compounding: &Compounding,
+) -> PyResult<Vec<BdtLevel>> {
+    let mut levels = levels(forward_curve, step_vols, dt)?;
+
+    let mut target = 1.0;
+    let mut state_prices = vec![1.0];
+    for (n, forward) in forward_curve.iter().enumerate() {
+        target *= compounding.discount_factor(*forward, dt);
+        let spacing = levels[n].spacing;
+        let spreads: Vec<f64> = (0..=n)
+            .map(|i| (spacing * (n as f64 - 2.0 * i as f64)).exp())
+            .collect();
+        let zero_price = |median: f64| -> f64 {
+            state_prices
+                .iter()
+                .zip(spreads.iter())
+                .map(|(q, s)| q * compounding.discount_factor(median * s, dt))
+                .sum()
+        };
+        let f = |median: f64| zero_price(median) - target;
+        let df = |median: f64| {
+            let h = 1e-7;
+            (zero_price(median + h) - zero_price(median - h)) / (2.0 * h)
+        };
+        let lower = (compounding.min_rate(dt) + 1e-8).max(-1.0) / spreads[0];
+        levels[n].median = root::solve(f, df, *forward, (lower, 10.0), 1e-14, 100)?;
+        state_prices =
+            Lattice::new(&levels, dt, *compounding, 0.0).roll_state_prices(n, &state_prices);
+    }
+
+    Ok(levels)
+}
+
+
+impl Level for BdtLevel {
+    fn nodes(&self) -> usize {
+        self.index + 1
+    }
+
+    fn rate(&self, node: usize) -> f64 {
+        self.median * (self.spacing * (self.index as f64 - 2.0 * node as f64)).exp()
+    }
+
+    fn branches(&self, node: usize) -> Branches {
+        Branches::Binomial(node, [0.5, 0.5])
+    }
+
+    // Rates step out from the median by exp(2 spacing), one exponential for the level
+    fn rates(&self) -> Vec<f64> {
+        let (up, down) = (self.spacing.exp(), (-self.spacing).exp());
+        let (mut high, mut low) = if self.index.is_multiple_of(2) {
+            (1.0, 1.0)
+        } else {
+            (up, down)
+        };
+        let mut rates = vec![0.0; self.nodes()];
+        for node in (0..=self.index / 2).rev() {
+            rates[node] = self.median * high;
+            rates[self.index - node] = self.median * low;
+            high *= up * up;
+            low *= down * down;
+        }
+
+        rates
+    }
+}
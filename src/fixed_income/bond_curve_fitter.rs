@@ -0,0 +1,373 @@
+/*
+Fits a discount curve directly to a basket of coupon bonds
+- Bonds are SimpleBond or FixedRateBond, cash flows in years from settlement
+- Curve model is Nelson-Siegel, Svensson or a spline on zero rates at chosen knots
+- Errors are weighted by price, or by duration so they approximate yield errors
+- Rich/cheap compares each bond's market yield with its yield at the model price
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::fixed_income::fixed_rate_bond::FixedRateBond;
+use crate::fixed_income::simple_bond::SimpleBond;
+use crate::rates::parametric::{Bounds, CurveFit, Model, NelsonSiegel, ParametricCurve, Svensson};
+use crate::rates::yield_curve::YieldCurve;
+use crate::solver::least_squares::levenberg_marquardt;
+use crate::solver::root;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FitModel {
+    Parametric(Model),
+    Spline,
+}
+
+impl FitModel {
+    fn parse(model: &str) -> PyResult<Self> {
+        match model {
+            "nelson_siegel" => Ok(FitModel::Parametric(Model::NelsonSiegel)),
+            "svensson" => Ok(FitModel::Parametric(Model::Svensson)),
+            "spline" => Ok(FitModel::Spline),
+            _ => Err(PyValueError::new_err(
+                "Invalid curve model. Use nelson_siegel, svensson or spline",
+            )),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Weighting {
+    Price,
+    Duration,
+}
+
+impl Weighting {
+    fn parse(weighting: &str) -> PyResult<Self> {
+        match weighting {
+            "price" => Ok(Weighting::Price),
+            "duration" => Ok(Weighting::Duration),
+            _ => Err(PyValueError::new_err("Invalid weighting. Use price or duration")),
+        }
+    }
+}
+
+
+// Either bond class can be passed in the basket
+#[derive(FromPyObject)]
+enum BasketBond<'a> {
+    Simple(PyRef<'a, SimpleBond>),
+    Fixed(PyRef<'a, FixedRateBond>),
+}
+
+impl BasketBond<'_> {
+    fn cash_flows(&self) -> Vec<(f64, f64)> {
+        match self {
+            BasketBond::Simple(bond) => bond.cash_flows(),
+            BasketBond::Fixed(bond) => bond.cash_flows(),
+        }
+    }
+
+    fn accrued(&self) -> f64 {
+        match self {
+            BasketBond::Simple(_) => 0.0,
+            BasketBond::Fixed(bond) => bond.accrued_interest(),
+        }
+    }
+}
+
+
+// Fitted curve before it is handed back to Python
+enum FittedCurve {
+    Parametric(ParametricCurve),
+    Spline(Box<YieldCurve>),
+}
+
+impl FittedCurve {
+    fn discount(&self, t: f64) -> f64 {
+        match self {
+            FittedCurve::Parametric(curve) => curve.discount(t),
+            FittedCurve::Spline(curve) => curve.discount(t),
+        }
+    }
+
+    fn into_object(self, py: Python<'_>) -> PyResult<PyObject> {
+        match self {
+            FittedCurve::Parametric(curve) => match curve.model {
+                Model::NelsonSiegel => Ok(Py::new(py, NelsonSiegel::from_curve(curve))?.into_py(py)),
+                Model::Svensson => Ok(Py::new(py, Svensson::from_curve(curve))?.into_py(py)),
+            },
+            FittedCurve::Spline(curve) => Ok(Py::new(py, *curve)?.into_py(py)),
+        }
+    }
+}
+
+
+#[pyclass]
+pub struct BondCurveFitter {
+    #[pyo3(get)]
+    model: String,
+    #[pyo3(get)]
+    weighting: String,
+    #[pyo3(get)]
+    knots: Option<Vec<f64>>,
+    #[pyo3(get)]
+    curve: PyObject,
+    #[pyo3(get)]
+    market_prices: Vec<f64>,
+    #[pyo3(get)]
+    model_prices: Vec<f64>,
+    #[pyo3(get)]
+    price_residuals: Vec<f64>,
+    #[pyo3(get)]
+    market_yields: Vec<f64>,
+    #[pyo3(get)]
+    model_yields: Vec<f64>,
+    #[pyo3(get)]
+    yield_residuals_bp: Vec<f64>,
+    #[pyo3(get)]
+    rich_cheap: Vec<String>,
+    #[pyo3(get)]
+    fit: CurveFit,
+}
+
+#[pymethods]
+impl BondCurveFitter {
+    // Prices are clean unless clean is false, yields are continuously compounded
+    #[new]
+    #[pyo3(signature = (
+        bonds,
+        prices,
+        model="nelson_siegel",
+        knots=None,
+        interpolation="cubic",
+        weighting="price",
+        clean=true,
+        fair_band_bp=0.5,
+        tol=1e-12,
+        max_iter=200,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        py: Python<'_>,
+        bonds: Vec<BasketBond>,
+        prices: Vec<f64>,
+        model: &str,
+        knots: Option<Vec<f64>>,
+        interpolation: &str,
+        weighting: &str,
+        clean: bool,
+        fair_band_bp: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<Self> {
+        let fit_model = FitModel::parse(model)?;
+        let weights_by = Weighting::parse(weighting)?;
+        let n = bonds.len();
+        if prices.len() != n || n == 0 {
+            return Err(PyValueError::new_err(
+                "Bonds and prices must have the same, non zero length",
+            ));
+        }
+        if prices.iter().any(|p| *p <= 0.0) {
+            return Err(PyValueError::new_err("Prices must be positive"));
+        }
+
+        let accrued: Vec<f64> = bonds
+            .iter()
+            .map(|bond| if clean { bond.accrued() } else { 0.0 })
+            .collect();
+        let dirty: Vec<f64> = prices.iter().zip(accrued.iter()).map(|(p, a)| p + a).collect();
+        let (times, amounts): (Vec<Vec<f64>>, Vec<Vec<f64>>) =
+            bonds.iter().map(|bond| bond.cash_flows().into_iter().unzip()).unzip();
+
+        // Duration weights turn price errors into approximate yield errors
+        let market_yields = (0..n)
+            .map(|i| flat_yield(&times[i], &amounts[i], dirty[i]))
+            .collect::<PyResult<Vec<f64>>>()?;
+        let weights: Vec<f64> = match weights_by {
+            Weighting::Price => vec![1.0; n],
+            Weighting::Duration => (0..n)
+                .map(|i| 1.0 / (dirty[i] * duration(&times[i], &amounts[i], market_yields[i])))
+                .collect(),
+        };
+
+        let (fitted, iterations, converged) = match fit_model {
+            FitModel::Parametric(parametric) => {
+                let curve = ParametricCurve::fit_prices(
+                    parametric,
+                    times.clone(),
+                    amounts.clone(),
+                    dirty.clone(),
+                    Some(weights),
+                    Bounds {
+                        lower: None,
+                        upper: None,
+                        initial: None,
+                    },
+                    tol,
+                    max_iter,
+                )?;
+                let (iterations, converged) = match &curve.fit {
+                    Some(fit) => (fit.iterations(), fit.converged()),
+                    None => (0, false),
+                };
+                (FittedCurve::Parametric(curve), iterations, converged)
+            }
+            FitModel::Spline => {
+                let knots = knots
+                    .clone()
+                    .ok_or_else(|| PyValueError::new_err("Spline model needs knot times"))?;
+                let (curve, iterations, converged) = fit_spline(
+                    knots,
+                    interpolation,
+                    &times,
+                    &amounts,
+                    &dirty,
+                    &weights,
+                    &market_yields,
+                    tol,
+                    max_iter,
+                )?;
+                (FittedCurve::Spline(Box::new(curve)), iterations, converged)
+            }
+        };
+
+        // Compare market and model on the quoted price basis
+        let model_dirty: Vec<f64> = (0..n)
+            .map(|i| times[i].iter().zip(amounts[i].iter()).map(|(t, cf)| cf * fitted.discount(*t)).sum())
+            .collect();
+        let model_yields = (0..n)
+            .map(|i| flat_yield(&times[i], &amounts[i], model_dirty[i]))
+            .collect::<PyResult<Vec<f64>>>()?;
+        let model_prices: Vec<f64> = model_dirty.iter().zip(accrued.iter()).map(|(p, a)| p - a).collect();
+        let price_residuals: Vec<f64> = prices.iter().zip(model_prices.iter()).map(|(m, p)| m - p).collect();
+        let yield_residuals_bp: Vec<f64> = market_yields
+            .iter()
+            .zip(model_yields.iter())
+            .map(|(m, y)| (m - y) * 10000.0)
+            .collect();
+        let rich_cheap = yield_residuals_bp
+            .iter()
+            .map(|bp| {
+                if bp.abs() <= fair_band_bp {
+                    "fair".to_string()
+                } else if *bp < 0.0 {
+                    "rich".to_string()
+                } else {
+                    "cheap".to_string()
+                }
+            })
+            .collect();
+
+        Ok(BondCurveFitter {
+            model: model.to_string(),
+            weighting: weighting.to_string(),
+            knots,
+            curve: fitted.into_object(py)?,
+            market_prices: prices,
+            model_prices,
+            price_residuals: price_residuals.clone(),
+            market_yields,
+            model_yields,
+            yield_residuals_bp,
+            rich_cheap,
+            fit: CurveFit::new(price_residuals.iter().map(|r| -r).collect(), iterations, converged),
+        })
+    }
+}
+
+
+// Least squares fit of the spline zero rates at the knots
+#[allow(clippy::too_many_arguments)]
+fn fit_spline(
+    knots: Vec<f64>,
+    interpolation: &str,
+    times: &[Vec<f64>],
+    amounts: &[Vec<f64>],
+    dirty: &[f64],
+    weights: &[f64],
+    market_yields: &[f64],
+    tol: f64,
+    max_iter: usize,
+) -> PyResult<(YieldCurve, usize, bool)> {
+    if knots.is_empty() || knots[0] <= 0.0 || knots.windows(2).any(|w| w[1] <= w[0]) {
+        return Err(PyValueError::new_err(
+            "Knot times must be positive and strictly increasing",
+        ));
+    }
+    if knots.len() > dirty.len() {
+        return Err(PyValueError::new_err(
+            "Spline model needs no more knots than bonds",
+        ));
+    }
+    let template = YieldCurve::from_zero_rates(
+        knots.clone(),
+        vec![0.0; knots.len()],
+        interpolation,
+        "zero_rate",
+        None,
+        "act/365",
+    )?;
+
+    let objective = |zero_rates: &[f64]| -> Vec<f64> {
+        let curve = template.with_zero_rates(zero_rates.to_vec()).unwrap();
+        (0..dirty.len())
+            .map(|i| {
+                let model_price: f64 = times[i]
+                    .iter()
+                    .zip(amounts[i].iter())
+                    .map(|(t, cf)| cf * curve.discount(*t))
+                    .sum();
+                weights[i] * (model_price - dirty[i])
+            })
+            .collect()
+    };
+
+    // Start flat at the average market yield
+    let average = market_yields.iter().sum::<f64>() / market_yields.len() as f64;
+    let initial = vec![average; knots.len()];
+    let lower = vec![-0.5; knots.len()];
+    let upper = vec![0.5; knots.len()];
+    let result = levenberg_marquardt(objective, &initial, &lower, &upper, tol, max_iter);
+
+    let curve = template.with_zero_rates(result.params)?;
+    Ok((curve, result.iterations, result.converged))
+}
+
+
+// Continuously compounded yield that discounts the cash flows to a dirty price
+fn flat_yield(times: &[f64], amounts: &[f64], price: f64) -> PyResult<f64> {
+    let f = |y: f64| -> f64 {
+        times
+            .iter()
+            .zip(amounts.iter())
+            .map(|(t, cf)| cf * (-y * t).exp())
+            .sum::<f64>()
+            - price
+    };
+    let df = |y: f64| -> f64 {
+        times
+            .iter()
+            .zip(amounts.iter())
+            .map(|(t, cf)| -t * cf * (-y * t).exp())
+            .sum()
+    };
+
+    root::solve(f, df, 0.03, (-1.0, 1.0), 1e-12, 100)
+}
+
+
+// Macaulay duration under continuous compounding
+fn duration(times: &[f64], amounts: &[f64], yield_rate: f64) -> f64 {
+    let (weighted, pv) = times
+        .iter()
+        .zip(amounts.iter())
+        .fold((0.0, 0.0), |(weighted, pv), (t, cf)| {
+            let value = cf * (-yield_rate * t).exp();
+            (weighted + t * value, pv + value)
+        });
+
+    weighted / pv
+}
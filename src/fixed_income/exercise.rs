@@ -0,0 +1,182 @@
+/*
+Exercise schedules and policies for the rate trees
+- Schedule entries are (time or date, price, american or bermudan)
+- American entries hold their price until the next entry, bermudan entries apply on their step alone
+- Nothing is exercisable before the first entry, european exercises on the first entry only
+- Without a schedule the option is exercisable on coupon dates, where node values are clean
+- A policy bounds a node value by the call price from above and the put price from below,
+  both dirty so between coupon dates they include the accrued coupon
+- Exercise probabilities roll the state prices of unexercised nodes forward; the state price of
+  the nodes first exercised on a step over the zero coupon price to that step is its probability
+*/
+
+use chrono::NaiveDate;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::dates::day_count::DayCount;
+use crate::fixed_income::lattice::{Lattice, Level};
+use crate::rates::yield_curve::CurveTime;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExerciseType {
+    American,
+    Bermudan,
+}
+
+impl ExerciseType {
+    fn parse(exercise: &str) -> PyResult<Self> {
+        match exercise {
+            "american" => Ok(ExerciseType::American),
+            "bermudan" => Ok(ExerciseType::Bermudan),
+            _ => Err(PyValueError::new_err(
+                "Invalid exercise type. Use american or bermudan",
+            )),
+        }
+    }
+}
+
+
+// Call and put prices in force on one step
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExercisePolicy {
+    pub call: Option<f64>,
+    pub put: Option<f64>,
+}
+
+impl ExercisePolicy {
+    // Issuer calls above the call price, then the holder puts below the put price
+    pub fn apply(&self, value: f64) -> f64 {
+        let called = self.call.map_or(value, |call| value.min(call));
+        self.put.map_or(called, |put| called.max(put))
+    }
+}
+
+
+// Exercise entries as times in years, dates measured act/365 from the valuation date
+pub fn parse_schedule(
+    schedule: Option<Vec<(CurveTime, f64, String)>>,
+    valuation_date: Option<NaiveDate>,
+) -> PyResult<Vec<(f64, f64, String)>> {
+    let mut entries = Vec::new();
+    for (time, price, exercise) in schedule.unwrap_or_default() {
+        ExerciseType::parse(&exercise)?;
+        let t = match time {
+            CurveTime::Time(t) => t,
+            CurveTime::Date(date) => match valuation_date {
+                Some(start) => DayCount::Act365.year_fraction(start, date, start, date, 1),
+                None => {
+                    return Err(PyValueError::new_err(
+                        "Schedule dates need a valuation date",
+                    ))
+                }
+            },
+        };
+        if t <= 0.0 {
+            return Err(PyValueError::new_err("Exercise times must be positive"));
+        }
+        entries.push((t, price, exercise));
+    }
+    entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    Ok(entries)
+}
+
+
+// Strike on each step, None where the option cannot be exercised
+// Without a schedule the price applies on each coupon level before maturity, or the last one if european
+pub fn step_strikes(
+    schedule: &[(f64, f64, String)],
+    price: f64,
+    european: bool,
+    coupon_levels: &[usize],
+    n_steps: usize,
+    dt: f64,
+) -> PyResult<Vec<Option<f64>>> {
+    let step = |t: f64| (t / dt).round() as usize;
+    let mut strikes = vec![None; n_steps];
+    if schedule.is_empty() {
+        let mut levels = coupon_levels
+            .iter()
+            .filter(|level| **level > 0 && **level < n_steps);
+        if european {
+            if let Some(level) = levels.next_back() {
+                strikes[*level] = Some(price);
+            }
+        } else {
+            for level in levels {
+                strikes[*level] = Some(price);
+            }
+        }
+        return Ok(strikes);
+    }
+
+    if schedule.iter().any(|(t, _, _)| step(*t) >= n_steps) {
+        return Err(PyValueError::new_err(
+            "Exercise times must fall before the last step",
+        ));
+    }
+    let entries = if european { &schedule[..1] } else { schedule };
+    for (k, (t, price, exercise)) in entries.iter().enumerate() {
+        let start = step(*t).max(1);
+        let end = match ExerciseType::parse(exercise)? {
+            ExerciseType::Bermudan => start + 1,
+            ExerciseType::American if european => start + 1,
+            ExerciseType::American => entries
+                .get(k + 1)
+                .map_or(n_steps, |(next, _, _)| step(*next).max(1)),
+        };
+        for strike in strikes.iter_mut().take(end).skip(start) {
+            *strike = Some(*price);
+        }
+    }
+
+    Ok(strikes)
+}
+
+
+// Probability of first exercise on each step, from the state prices of unexercised nodes
+// normalised by the zero coupon price to the step, and the zero coupon prices to each step end
+pub fn exercise_probabilities<L: Level>(
+    lattice: &Lattice<L>,
+    exercise_map: &[Vec<bool>],
+) -> (Vec<f64>, Vec<f64>) {
+    let mut state_prices = vec![1.0];
+    let mut alive = vec![1.0];
+    let mut zero_price = 1.0;
+    let mut probabilities = Vec::with_capacity(exercise_map.len());
+    let mut zero_prices = Vec::with_capacity(exercise_map.len());
+    for (n, exercised) in exercise_map.iter().enumerate() {
+        let exercised_price = alive
+            .iter()
+            .zip(exercised.iter())
+            .filter(|(_, exercised)| **exercised)
+            .fold(0.0, |total, (q, _)| total + q);
+        probabilities.push(exercised_price / zero_price);
+        let discounts = lattice.discounts(n);
+        alive = lattice.roll_forward(
+            n,
+            &alive,
+            |j, q| if exercised[j] { 0.0 } else { q * discounts[j] },
+        );
+        state_prices = lattice.roll_forward(n, &state_prices, |j, q| q * discounts[j]);
+        zero_price = state_prices.iter().sum();
+        zero_prices.push(zero_price);
+    }
+
+    (probabilities, zero_prices)
+}
+
+
+// Expected time the bond is outstanding, exercise on step n ends it at n dt
+pub fn expected_life(probabilities: &[f64], dt: f64) -> f64 {
+    let maturity = probabilities.len() as f64 * dt;
+    let exercised: f64 = probabilities.iter().sum();
+    probabilities
+        .iter()
+        .enumerate()
+        .map(|(n, p)| p * n as f64 * dt)
+        .sum::<f64>()
+        + (1.0 - exercised) * maturity
+}
@@ -0,0 +1,394 @@
+/*
+Calculates the price of a dated fixed rate bond on any settlement date
+- Coupons follow a schedule from the issue date to maturity
+- Accrued interest is measured on unadjusted coupon dates with the bond day count
+- Yield is stated with compounding at the coupon frequency (street convention) by default
+*/
+
+use chrono::NaiveDate;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::dates::calendar::{add_months, Calendar};
+use crate::dates::day_count::DayCount;
+use crate::dates::schedule::Schedule;
+use crate::rates::compounding::Compounding;
+use crate::rates::yield_curve::YieldCurve;
+use crate::solver::root;
+
+
+#[pyclass]
+pub struct FixedRateBond {
+    #[pyo3(get)]
+    issue_date: NaiveDate,
+    #[pyo3(get)]
+    maturity_date: NaiveDate,
+    #[pyo3(get)]
+    coupon_rate: f64,
+    #[pyo3(get)]
+    frequency: u32,
+    #[pyo3(get)]
+    day_count: String,
+    #[pyo3(get)]
+    settlement_date: NaiveDate,
+    #[pyo3(get)]
+    yield_rate: f64,
+    #[pyo3(get)]
+    compounding: Compounding,
+    #[pyo3(get)]
+    notional: f64,
+    #[pyo3(get)]
+    schedule: Schedule,
+    #[pyo3(get)]
+    coupons: Vec<f64>,
+    #[pyo3(get)]
+    accrued_interest: f64,
+    #[pyo3(get)]
+    dirty_price: f64,
+    #[pyo3(get)]
+    clean_price: f64,
+    basis: DayCount,
+}
+
+#[pymethods]
+impl FixedRateBond {
+    #[new]
+    #[pyo3(signature = (
+        issue_date,
+        maturity_date,
+        coupon_rate,
+        frequency,
+        day_count,
+        settlement_date,
+        yield_rate,
+        notional=100.0,
+        calendar=None,
+        adjustment="following",
+        stub="short_front",
+        end_of_month=false,
+        compounding=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        issue_date: NaiveDate,
+        maturity_date: NaiveDate,
+        coupon_rate: f64,
+        frequency: u32,
+        day_count: &str,
+        settlement_date: NaiveDate,
+        yield_rate: f64,
+        notional: f64,
+        calendar: Option<Calendar>,
+        adjustment: &str,
+        stub: &str,
+        end_of_month: bool,
+        compounding: Option<Compounding>,
+    ) -> Result<Self, PyErr> {
+        let basis = DayCount::parse(day_count)?;
+        let schedule = Schedule::new(
+            issue_date,
+            maturity_date,
+            frequency,
+            calendar,
+            adjustment,
+            stub,
+            end_of_month,
+        )?;
+
+        let mut bond = FixedRateBond {
+            issue_date,
+            maturity_date,
+            coupon_rate,
+            frequency,
+            day_count: day_count.to_string(),
+            settlement_date,
+            yield_rate,
+            compounding: Compounding::periodic(frequency)?,
+            notional,
+            schedule,
+            coupons: Vec::new(),
+            accrued_interest: 0.0,
+            dirty_price: 0.0,
+            clean_price: 0.0,
+            basis,
+        };
+        if let Some(compounding) = compounding {
+            bond.compounding = compounding;
+        }
+        bond.check_settlement(settlement_date)?;
+        bond.calc_coupons();
+        bond.calculate();
+
+        Ok(bond)
+    }
+
+    pub fn calculate(&mut self) {
+        self.accrued_interest = self.calc_accrued(self.settlement_date);
+        self.dirty_price = self.calc_dirty_price(self.yield_rate, self.settlement_date, &self.compounding);
+        self.clean_price = self.dirty_price - self.accrued_interest;
+    }
+
+    // Accrued interest on a settlement date, defaults to the bond settlement date
+    #[pyo3(signature = (settlement_date=None))]
+    fn accrued(&self, settlement_date: Option<NaiveDate>) -> PyResult<f64> {
+        let settle = settlement_date.unwrap_or(self.settlement_date);
+        self.check_settlement(settle)?;
+
+        Ok(self.calc_accrued(settle))
+    }
+
+    // Price from a yield on a settlement date, clean by default
+    #[pyo3(signature = (yield_rate, settlement_date=None, clean=true, compounding=None))]
+    fn price_from_yield(
+        &self,
+        yield_rate: f64,
+        settlement_date: Option<NaiveDate>,
+        clean: bool,
+        compounding: Option<Compounding>,
+    ) -> PyResult<f64> {
+        let settle = settlement_date.unwrap_or(self.settlement_date);
+        self.check_settlement(settle)?;
+
+        let compounding = compounding.unwrap_or(self.compounding);
+        let dirty = self.calc_dirty_price(yield_rate, settle, &compounding);
+        if clean {
+            Ok(dirty - self.calc_accrued(settle))
+        } else {
+            Ok(dirty)
+        }
+    }
+
+    // Price by discounting the remaining cash flows on a yield curve
+    // Times run from the curve reference date, or from settlement when the curve has none
+    #[pyo3(signature = (curve, settlement_date=None, clean=true))]
+    pub fn price_from_curve(
+        &self,
+        curve: &YieldCurve,
+        settlement_date: Option<NaiveDate>,
+        clean: bool,
+    ) -> PyResult<f64> {
+        let settle = settlement_date.unwrap_or(self.settlement_date);
+        self.check_settlement(settle)?;
+
+        let time = |date: NaiveDate| -> PyResult<f64> {
+            match curve.reference_date() {
+                Some(_) => curve.date_to_time(date),
+                None => Ok(DayCount::Act365.year_fraction(settle, date, settle, date, 1)),
+            }
+        };
+
+        let mut pv = 0.0;
+        for (date, cf) in self.dated_cash_flows(settle) {
+            pv += cf * curve.discount(time(date)?);
+        }
+        let dirty = pv / curve.discount(time(settle)?);
+        if clean {
+            Ok(dirty - self.calc_accrued(settle))
+        } else {
+            Ok(dirty)
+        }
+    }
+
+    // Yield from a clean or dirty price, with the bond compounding unless given
+    #[pyo3(signature = (
+        price,
+        settlement_date=None,
+        clean=true,
+        compounding=None,
+        tol=1e-10,
+        max_iter=100,
+    ))]
+    fn yield_from_price(
+        &self,
+        price: f64,
+        settlement_date: Option<NaiveDate>,
+        clean: bool,
+        compounding: Option<Compounding>,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<f64> {
+        let settle = settlement_date.unwrap_or(self.settlement_date);
+        self.check_settlement(settle)?;
+        if price <= 0.0 {
+            return Err(PyValueError::new_err("Price must be positive"));
+        }
+
+        let compounding = compounding.unwrap_or(self.compounding);
+        let dirty = if clean {
+            price + self.calc_accrued(settle)
+        } else {
+            price
+        };
+        let cash_flows = self.street_cash_flows(settle);
+        let freq = self.frequency as f64;
+
+        // Price error and its derivative with times in years
+        let f = |y: f64| -> f64 {
+            cash_flows
+                .iter()
+                .map(|(periods, cf)| cf * compounding.discount_factor(y, periods / freq))
+                .sum::<f64>()
+                - dirty
+        };
+        let df = |y: f64| -> f64 {
+            cash_flows
+                .iter()
+                .map(|(periods, cf)| cf * compounding.discount_derivatives(y, periods / freq).1)
+                .sum()
+        };
+
+        let horizon = cash_flows.iter().fold(0.0, |last, (periods, _)| f64::max(last, periods / freq));
+        let lower = (compounding.min_rate(horizon) + 1e-8).max(-10.0);
+        root::solve(f, df, self.coupon_rate, (lower, 10.0), tol, max_iter)
+    }
+}
+
+impl FixedRateBond {
+    // Remaining cash flows with act/365 times in years from the settlement date
+    pub fn cash_flows(&self) -> Vec<(f64, f64)> {
+        let settle = self.settlement_date;
+        self.dated_cash_flows(settle)
+            .iter()
+            .map(|(date, cf)| (DayCount::Act365.year_fraction(settle, *date, settle, *date, 1), *cf))
+            .collect()
+    }
+
+    pub fn accrued_interest(&self) -> f64 {
+        self.accrued_interest
+    }
+
+    fn check_settlement(&self, settle: NaiveDate) -> Result<(), PyErr> {
+        if settle < self.issue_date || settle >= self.maturity_date {
+            return Err(PyValueError::new_err(
+                "Settlement date must be on or after issue and before maturity",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Coupon paid at the end of each accrual period
+    fn calc_coupons(&mut self) {
+        let dates = self.schedule.unadjusted();
+        self.coupons = dates
+            .windows(2)
+            .map(|period| self.coupon_amount(period[0], period[1], period[1]))
+            .collect();
+    }
+
+    // Coupon accrued from the start of an accrual period up to a date within it
+    fn coupon_amount(&self, start: NaiveDate, end: NaiveDate, upto: NaiveDate) -> f64 {
+        let freq = self.frequency as f64;
+        let periods = if self.basis == DayCount::ActActIcma {
+            self.notional_periods(start, end)
+                .iter()
+                .map(|(period_start, period_end)| {
+                    let from = start.max(*period_start);
+                    let to = upto.min(*period_end);
+                    if to <= from {
+                        0.0
+                    } else {
+                        self.basis.days(from, to) / self.basis.days(*period_start, *period_end)
+                    }
+                })
+                .sum()
+        } else {
+            self.basis.year_fraction(start, upto, start, end, self.frequency) * freq
+        };
+
+        self.notional * self.coupon_rate / freq * periods
+    }
+
+    // Regular coupon periods covering an accrual period, stubs are rolled from the regular side
+    fn notional_periods(&self, start: NaiveDate, end: NaiveDate) -> Vec<(NaiveDate, NaiveDate)> {
+        let step = self.schedule.months_per_period();
+        let eom = self.schedule.end_of_month();
+        let mut periods = Vec::new();
+        if self.schedule.has_front_stub() {
+            let mut period_end = end;
+            loop {
+                let period_start = add_months(period_end, -step, eom);
+                periods.push((period_start, period_end));
+                if period_start <= start {
+                    break;
+                }
+                period_end = period_start;
+            }
+            periods.reverse();
+        } else {
+            let mut period_start = start;
+            loop {
+                let period_end = add_months(period_start, step, eom);
+                periods.push((period_start, period_end));
+                if period_end >= end {
+                    break;
+                }
+                period_start = period_end;
+            }
+        }
+
+        periods
+    }
+
+    // Index of the accrual period containing the settlement date
+    fn current_period(&self, settle: NaiveDate) -> usize {
+        let dates = self.schedule.unadjusted();
+        dates[1..].iter().position(|date| *date > settle).unwrap()
+    }
+
+    fn calc_accrued(&self, settle: NaiveDate) -> f64 {
+        let dates = self.schedule.unadjusted();
+        let i = self.current_period(settle);
+
+        self.coupon_amount(dates[i], dates[i + 1], settle)
+    }
+
+    // Cash flows from the next coupon onwards with their street convention exponents
+    fn street_cash_flows(&self, settle: NaiveDate) -> Vec<(f64, f64)> {
+        let dates = self.schedule.unadjusted();
+        let i = self.current_period(settle);
+        let next = dates[i + 1];
+        let regular_start = add_months(
+            next,
+            -self.schedule.months_per_period(),
+            self.schedule.end_of_month(),
+        );
+        let w = self.basis.days(settle, next) / self.basis.days(regular_start, next);
+
+        let n = self.coupons.len();
+        (i..n)
+            .map(|k| {
+                let mut cf = self.coupons[k];
+                if k == n - 1 {
+                    cf += self.notional;
+                }
+                (w + (k - i) as f64, cf)
+            })
+            .collect()
+    }
+
+    // Remaining cash flows on their adjusted payment dates
+    fn dated_cash_flows(&self, settle: NaiveDate) -> Vec<(NaiveDate, f64)> {
+        let payments = self.schedule.payments();
+        let i = self.current_period(settle);
+        let n = self.coupons.len();
+        (i..n)
+            .map(|k| {
+                let mut cf = self.coupons[k];
+                if k == n - 1 {
+                    cf += self.notional;
+                }
+                (payments[k], cf)
+            })
+            .collect()
+    }
+
+    // Street convention discounts over coupon periods, periodic(frequency) gives (1 + y / f)^-n
+    fn calc_dirty_price(&self, yield_rate: f64, settle: NaiveDate, compounding: &Compounding) -> f64 {
+        let freq = self.frequency as f64;
+        self.street_cash_flows(settle)
+            .iter()
+            .map(|(periods, cf)| cf * compounding.discount_factor(yield_rate, periods / freq))
+            .sum()
+    }
+}
@@ -0,0 +1,186 @@
+/*
+Hull-White one factor trinomial tree
+- Short rate dr = (theta(t) - a r) dt + sigma dW with a normal vol sigma
+- Stage one builds the tree for the mean reverting state x with spacing sigma sqrt(3 dt)
+- Branching switches to pure up or down at j_max = ceil(0.184 / (a dt)) so the tree stays bounded
+- Stage two shifts each level by alpha so the tree reprices the input zero coupon bonds
+- Levels only hold their spacing, branching and shift, rates and probabilities are rebuilt per node
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::fixed_income::lattice::{Branches, Lattice, Level};
+use crate::rates::compounding::Compounding;
+use crate::solver::root;
+
+
+#[derive(Clone)]
+#[pyclass]
+pub struct TrinomialTreeLevel {
+    #[pyo3(get)]
+    pub prices: Vec<f64>,
+    #[pyo3(get)]
+    pub rates: Vec<f64>,
+    #[pyo3(get)]
+    pub coupon: f64,
+    #[pyo3(get)]
+    pub alpha: f64,
+    // Index of the lowest child in the next level and the down, middle, up probabilities
+    #[pyo3(get)]
+    pub children: Vec<usize>,
+    #[pyo3(get)]
+    pub probabilities: Vec<(f64, f64, f64)>,
+}
+
+
+// Spacing, branching and shift of one level, its nodes are rebuilt when needed
+#[derive(Clone, Copy, Debug)]
+pub struct HullWhiteLevel {
+    width: i64,
+    next_width: i64,
+    j_max: i64,
+    m: f64,
+    dx: f64,
+    alpha: f64,
+}
+
+impl HullWhiteLevel {
+    // Full level with its node values, as kept on the bond
+    pub fn expand(&self, coupon: f64, prices: Vec<f64>) -> TrinomialTreeLevel {
+        let nodes = 0..self.nodes();
+        TrinomialTreeLevel {
+            prices,
+            rates: nodes.clone().map(|j| self.rate(j)).collect(),
+            coupon,
+            alpha: self.alpha,
+            children: nodes.clone().map(|j| self.branches(j).first()).collect(),
+            probabilities: nodes
+                .map(|j| {
+                    let (_, (down, middle, up)) =
+                        branching(j as i64 - self.width, self.j_max, self.m);
+                    (down, middle, up)
+                })
+                .collect(),
+        }
+    }
+}
+
+
+// Build the levels on one period forwards and fit each to the curve
+pub fn build_levels(
+    forward_curve: &[f64],
+    mean_reversion: f64,
+    sigma: f64,
+    dt: f64,
+    compounding: &Compounding,
+) -> PyResult<Vec<HullWhiteLevel>> {
+    if mean_reversion < 0.0 || sigma <= 0.0 {
+        return Err(PyValueError::new_err(
+            "Mean reversion must not be negative and vol must be positive",
+        ));
+    }
+
+    let n_steps = forward_curve.len();
+    let dx = sigma * (3.0 * dt).sqrt();
+    let m = (-mean_reversion * dt).exp_m1();
+    let j_max = if mean_reversion > 0.0 {
+        ((0.184 / (mean_reversion * dt)).ceil() as i64).max(1)
+    } else {
+        n_steps as i64
+    };
+
+    // Stage one, node offsets j and branching for the state x = r - alpha
+    let mut levels: Vec<HullWhiteLevel> = (0..n_steps)
+        .map(|i| HullWhiteLevel {
+            width: (i as i64).min(j_max),
+            next_width: (i as i64 + 1).min(j_max),
+            j_max,
+            m,
+            dx,
+            alpha: 0.0,
+        })
+        .collect();
+
+    // Stage two, shift each level so its zero coupon bond matches the curve
+    let mut target = 1.0;
+    let mut state_prices = vec![1.0];
+    for (i, forward) in forward_curve.iter().enumerate() {
+        target *= compounding.discount_factor(*forward, dt);
+        let offsets: Vec<f64> = (-levels[i].width..=levels[i].width)
+            .map(|j| j as f64 * dx)
+            .collect();
+        let zero_price = |alpha: f64| -> f64 {
+            state_prices
+                .iter()
+                .zip(offsets.iter())
+                .map(|(q, x)| q * compounding.discount_factor(alpha + x, dt))
+                .sum()
+        };
+        let f = |alpha: f64| zero_price(alpha) - target;
+        let df = |alpha: f64| {
+            let h = 1e-7;
+            (zero_price(alpha + h) - zero_price(alpha - h)) / (2.0 * h)
+        };
+        let alpha = root::solve(f, df, *forward, (-1.0, 10.0), 1e-14, 100)?;
+
+        levels[i].alpha = alpha;
+        state_prices =
+            Lattice::new(&levels, dt, *compounding, 0.0).roll_state_prices(i, &state_prices);
+    }
+
+    Ok(levels)
+}
+
+
+// Down, middle and up probabilities from node j and the index offset of the lowest child from j
+fn branching(j: i64, j_max: i64, m: f64) -> (i64, (f64, f64, f64)) {
+    let jm = j as f64 * m;
+    let jm2 = jm * jm;
+    if j >= j_max {
+        // Branch down to j, j - 1, j - 2
+        (
+            j - 2,
+            (
+                1.0 / 6.0 + (jm2 + jm) / 2.0,
+                -1.0 / 3.0 - jm2 - 2.0 * jm,
+                7.0 / 6.0 + (jm2 + 3.0 * jm) / 2.0,
+            ),
+        )
+    } else if j <= -j_max {
+        // Branch up to j, j + 1, j + 2
+        (
+            j,
+            (
+                7.0 / 6.0 + (jm2 - 3.0 * jm) / 2.0,
+                -1.0 / 3.0 - jm2 + 2.0 * jm,
+                1.0 / 6.0 + (jm2 - jm) / 2.0,
+            ),
+        )
+    } else {
+        (
+            j - 1,
+            (
+                1.0 / 6.0 + (jm2 - jm) / 2.0,
+                2.0 / 3.0 - jm2,
+                1.0 / 6.0 + (jm2 + jm) / 2.0,
+            ),
+        )
+    }
+}
+
+
+impl Level for HullWhiteLevel {
+    fn nodes(&self) -> usize {
+        (2 * self.width + 1) as usize
+    }
+
+    fn rate(&self, node: usize) -> f64 {
+        self.alpha + (node as i64 - self.width) as f64 * self.dx
+    }
+
+    fn branches(&self, node: usize) -> Branches {
+        let (k, (down, middle, up)) = branching(node as i64 - self.width, self.j_max, self.m);
+        Branches::Trinomial((k + self.next_width) as usize, [down, middle, up])
+    }
+}
@@ -0,0 +1,139 @@
+/*
+Key rate durations and bucketed PV01 against a yield curve
+- Tent bumps are triangular zero rate bumps at key times, adding up to a parallel shift
+- Pillar bumps move one curve zero rate and let the interpolator re-fit the curve
+- Each instrument is repriced up and down the bump, sensitivities by central difference
+- Durations are on the dirty price, PV01 is the price change for a 1bp fall in the key rate
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::fixed_income::fixed_rate_bond::FixedRateBond;
+use crate::fixed_income::option_embedded_bond::OptionEmbeddedBond;
+use crate::fixed_income::simple_bond::SimpleBond;
+use crate::rates::yield_curve::YieldCurve;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BumpMethod {
+    Tent,
+    Pillar,
+}
+
+impl BumpMethod {
+    fn parse(method: &str) -> PyResult<Self> {
+        match method {
+            "tent" => Ok(BumpMethod::Tent),
+            "pillar" => Ok(BumpMethod::Pillar),
+            _ => Err(PyValueError::new_err("Invalid bump method. Use tent or pillar")),
+        }
+    }
+}
+
+
+// Straight and option embedded bonds priced off the curve
+#[derive(FromPyObject)]
+enum RiskInstrument<'a> {
+    Simple(PyRef<'a, SimpleBond>),
+    Fixed(PyRef<'a, FixedRateBond>),
+    OptionEmbedded(PyRef<'a, OptionEmbeddedBond>),
+}
+
+impl RiskInstrument<'_> {
+    fn price(&self, curve: &YieldCurve) -> PyResult<f64> {
+        match self {
+            RiskInstrument::Simple(bond) => Ok(bond.price_from_curve(curve)),
+            RiskInstrument::Fixed(bond) => bond.price_from_curve(curve, None, false),
+            RiskInstrument::OptionEmbedded(bond) => bond.price_from_curve(curve),
+        }
+    }
+}
+
+
+#[pyclass]
+pub struct KeyRateRisk {
+    #[pyo3(get)]
+    key_times: Vec<f64>,
+    #[pyo3(get)]
+    method: String,
+    #[pyo3(get)]
+    bump: f64,
+    #[pyo3(get)]
+    prices: Vec<f64>,
+    #[pyo3(get)]
+    key_rate_durations: Vec<Vec<f64>>,
+    #[pyo3(get)]
+    bucketed_pv01: Vec<Vec<f64>>,
+    #[pyo3(get)]
+    total_durations: Vec<f64>,
+}
+
+#[pymethods]
+impl KeyRateRisk {
+    // Key times default to the curve pillars, pillar bumps only work on the pillars
+    #[new]
+    #[pyo3(signature = (curve, instruments, key_times=None, method="tent", bump=0.0001))]
+    fn new(
+        curve: &YieldCurve,
+        instruments: Vec<RiskInstrument>,
+        key_times: Option<Vec<f64>>,
+        method: &str,
+        bump: f64,
+    ) -> PyResult<Self> {
+        let bump_method = BumpMethod::parse(method)?;
+        if bump <= 0.0 {
+            return Err(PyValueError::new_err("Bump size must be positive"));
+        }
+        let key_times = key_times.unwrap_or_else(|| curve.times().to_vec());
+        if bump_method == BumpMethod::Pillar && key_times != curve.times() {
+            return Err(PyValueError::new_err(
+                "Pillar bumps need the key times to be the curve times",
+            ));
+        }
+
+        // Bumped curves are shared by every instrument
+        let mut bumped = Vec::with_capacity(key_times.len());
+        for k in 0..key_times.len() {
+            let bump_curve = |size: f64| -> PyResult<YieldCurve> {
+                match bump_method {
+                    BumpMethod::Tent => curve.key_rate_bumped(key_times.clone(), k, size),
+                    BumpMethod::Pillar => {
+                        let mut zero_rates = curve.zero_rates().to_vec();
+                        zero_rates[k] += size;
+                        curve.with_zero_rates(zero_rates)
+                    }
+                }
+            };
+            bumped.push((bump_curve(bump)?, bump_curve(-bump)?));
+        }
+
+        let mut prices = Vec::with_capacity(instruments.len());
+        let mut key_rate_durations = Vec::with_capacity(instruments.len());
+        let mut bucketed_pv01 = Vec::with_capacity(instruments.len());
+        for instrument in instruments.iter() {
+            let price = instrument.price(curve)?;
+            let mut durations = Vec::with_capacity(key_times.len());
+            let mut pv01 = Vec::with_capacity(key_times.len());
+            for (up, down) in bumped.iter() {
+                let change = instrument.price(down)? - instrument.price(up)?;
+                durations.push(change / (2.0 * bump * price));
+                pv01.push(change / (2.0 * bump) * 0.0001);
+            }
+            prices.push(price);
+            key_rate_durations.push(durations);
+            bucketed_pv01.push(pv01);
+        }
+        let total_durations = key_rate_durations.iter().map(|d| d.iter().sum()).collect();
+
+        Ok(KeyRateRisk {
+            key_times,
+            method: method.to_string(),
+            bump,
+            prices,
+            key_rate_durations,
+            bucketed_pv01,
+            total_durations,
+        })
+    }
+}
@@ -0,0 +1,293 @@
+/*
+Generic recombining short rate lattice
+- Each level is one time step, its nodes hold a short rate and branch into the next level
+- Binomial nodes branch to two children and trinomial nodes to three, each with a probability
+- A node value is discounted over its step at the node rate plus a spread
+- Rollback starts from a payoff on any level, adds each node's cash flow before discounting
+  and hands every discounted value to an exercise function
+- Each level's discount factors are computed once per pass and shared by the values rolled on it
+- The in place rollback keeps two levels of values, memory grows with the width not the size,
+  and can roll the values without exercise alongside
+- State prices and exercise probabilities roll forward through the same branches
+- One year forwards are spread onto steps by interpolating between mid-year points, then
+  shifted so each year's steps compound back to its forward
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::interpolate::cubic_spline::CubicSpline;
+use crate::interpolate::linear_spline::LinearSpline;
+use crate::rates::compounding::Compounding;
+
+
+// Children of a node, the index of the first child in the next level and the probabilities
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Branches {
+    Binomial(usize, [f64; 2]),
+    Trinomial(usize, [f64; 3]),
+}
+
+impl Branches {
+    pub fn iter(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let (first, probabilities): (usize, &[f64]) = match self {
+            Branches::Binomial(first, probabilities) => (*first, probabilities),
+            Branches::Trinomial(first, probabilities) => (*first, probabilities),
+        };
+        probabilities
+            .iter()
+            .enumerate()
+            .map(move |(k, p)| (first + k, *p))
+    }
+
+    pub fn expectation(&self, next: &[f64]) -> f64 {
+        self.iter().map(|(k, p)| p * next[k]).sum()
+    }
+
+    pub fn first(&self) -> usize {
+        match self {
+            Branches::Binomial(first, _) | Branches::Trinomial(first, _) => *first,
+        }
+    }
+
+    fn last(&self) -> usize {
+        match self {
+            Branches::Binomial(first, _) => first + 1,
+            Branches::Trinomial(first, _) => first + 2,
+        }
+    }
+}
+
+
+// Node state of one level
+pub trait Level {
+    fn nodes(&self) -> usize;
+    fn rate(&self, node: usize) -> f64;
+    fn branches(&self, node: usize) -> Branches;
+
+    // Rates of every node, levels with a cheaper form of their rates override it
+    fn rates(&self) -> Vec<f64> {
+        (0..self.nodes()).map(|j| self.rate(j)).collect()
+    }
+}
+
+
+pub struct Lattice<'a, L: Level> {
+    levels: &'a [L],
+    dt: f64,
+    compounding: Compounding,
+    spread: f64,
+}
+
+impl<'a, L: Level> Lattice<'a, L> {
+    pub fn new(levels: &'a [L], dt: f64, compounding: Compounding, spread: f64) -> Self {
+        Lattice {
+            levels,
+            dt,
+            compounding,
+            spread,
+        }
+    }
+
+    // Nodes on a level, the maturity level after the last one included
+    pub fn nodes(&self, level: usize) -> usize {
+        match self.levels.get(level) {
+            Some(current) => current.nodes(),
+            None => {
+                let last = &self.levels[level - 1];
+                (0..last.nodes())
+                    .map(|j| last.branches(j).last() + 1)
+                    .max()
+                    .unwrap_or(1)
+            }
+        }
+    }
+
+    // Discount factors of every node on a level
+    pub fn discounts(&self, level: usize) -> Vec<f64> {
+        self.levels[level]
+            .rates()
+            .iter()
+            .map(|rate| {
+                self.compounding
+                    .discount_factor(rate + self.spread, self.dt)
+            })
+            .collect()
+    }
+
+    // Node values on levels to up to, not including, from; the payoff gives the values on level from
+    pub fn roll_back<P, C, E>(
+        &self,
+        from: usize,
+        to: usize,
+        payoff: P,
+        cash_flow: C,
+        mut exercise: E,
+    ) -> Vec<Vec<f64>>
+    where
+        P: Fn(usize) -> f64,
+        C: Fn(usize, usize) -> f64,
+        E: FnMut(usize, usize, f64) -> f64,
+    {
+        let terminal: Vec<f64> = (0..self.nodes(from)).map(payoff).collect();
+        let mut values: Vec<Vec<f64>> = Vec::with_capacity(from - to);
+        for level in (to..from).rev() {
+            let next = values.last().unwrap_or(&terminal);
+            let current = self
+                .discounts(level)
+                .iter()
+                .enumerate()
+                .map(|(j, discount)| {
+                    let expected = self.levels[level].branches(j).expectation(next);
+                    exercise(level, j, (expected + cash_flow(level, j)) * discount)
+                })
+                .collect();
+            values.push(current);
+        }
+        values.reverse();
+
+        values
+    }
+
+    // Root value holding two levels of values at a time, visit sees the values of each level
+    // With straight the values without exercise roll on the same discounts and their root is returned
+    pub fn roll_back_in_place<P, C, E, V>(
+        &self,
+        from: usize,
+        payoff: P,
+        cash_flow: C,
+        mut exercise: E,
+        straight: bool,
+        mut visit: V,
+    ) -> (f64, Option<f64>)
+    where
+        P: Fn(usize) -> f64,
+        C: Fn(usize, usize) -> f64,
+        E: FnMut(usize, usize, f64) -> f64,
+        V: FnMut(usize, &[f64]),
+    {
+        let mut next: Vec<f64> = (0..self.nodes(from)).map(payoff).collect();
+        let mut current = Vec::with_capacity(next.len());
+        let mut straight_next = if straight { next.clone() } else { Vec::new() };
+        let mut straight_current = Vec::with_capacity(straight_next.len());
+        for level in (0..from).rev() {
+            let discounts = self.discounts(level);
+            current.clear();
+            current.extend(discounts.iter().enumerate().map(|(j, discount)| {
+                let expected = self.levels[level].branches(j).expectation(&next);
+                exercise(level, j, (expected + cash_flow(level, j)) * discount)
+            }));
+            if straight {
+                straight_current.clear();
+                straight_current.extend(discounts.iter().enumerate().map(|(j, discount)| {
+                    let expected = self.levels[level].branches(j).expectation(&straight_next);
+                    (expected + cash_flow(level, j)) * discount
+                }));
+                std::mem::swap(&mut straight_current, &mut straight_next);
+            }
+            visit(level, &current);
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        (next[0], straight.then(|| straight_next[0]))
+    }
+
+    // Values one level on, each node passing its weighted value to its children
+    pub fn roll_forward<W>(&self, level: usize, values: &[f64], weight: W) -> Vec<f64>
+    where
+        W: Fn(usize, f64) -> f64,
+    {
+        let mut next = vec![0.0; self.nodes(level + 1)];
+        for (j, value) in values.iter().enumerate() {
+            let weighted = weight(j, *value);
+            for (k, p) in self.levels[level].branches(j).iter() {
+                next[k] += weighted * p;
+            }
+        }
+
+        next
+    }
+
+    // Arrow-Debreu prices one level on
+    pub fn roll_state_prices(&self, level: usize, state_prices: &[f64]) -> Vec<f64> {
+        let discounts = self.discounts(level);
+        self.roll_forward(level, state_prices, |j, q| q * discounts[j])
+    }
+
+    // Zero coupon bond prices to the end of each level, the sum of the state prices one level on
+    pub fn zero_coupon_prices(&self) -> Vec<f64> {
+        let mut state_prices = vec![1.0];
+        (0..self.levels.len())
+            .map(|level| {
+                state_prices = self.roll_state_prices(level, &state_prices);
+                state_prices.iter().sum()
+            })
+            .collect()
+    }
+}
+
+
+// One period forwards on a grid of steps_per_year steps a year from one year forwards
+pub fn step_forwards(
+    forward_curve: &[f64],
+    steps_per_year: usize,
+    interpolation: &str,
+    compounding: &Compounding,
+) -> PyResult<Vec<f64>> {
+    if steps_per_year == 1 {
+        return Ok(forward_curve.to_vec());
+    }
+
+    // Forwards sit at the middle of their year and are held flat beyond the first and last
+    let n_years = forward_curve.len();
+    let dt = 1.0 / steps_per_year as f64;
+    let mids: Vec<f64> = (0..n_years).map(|k| k as f64 + 0.5).collect();
+    let forward_at: Box<dyn Fn(f64) -> Option<f64>> = match (interpolation, n_years) {
+        ("linear" | "cubic", 1) => Box::new(|_| Some(forward_curve[0])),
+        ("cubic", 2) => {
+            return Err(PyValueError::new_err(
+                "Cubic interpolation needs at least three forwards, use linear",
+            ))
+        }
+        ("linear", _) => {
+            let spline = LinearSpline::new(mids.clone(), forward_curve.to_vec())?;
+            Box::new(move |t| spline.value(t))
+        }
+        ("cubic", _) => {
+            let spline = CubicSpline::new(mids.clone(), forward_curve.to_vec());
+            Box::new(move |t| spline.value(t))
+        }
+        _ => {
+            return Err(PyValueError::new_err(
+                "Invalid interpolation method. Use linear or cubic",
+            ))
+        }
+    };
+    let shape: Vec<f64> = (0..n_years * steps_per_year)
+        .map(|m| {
+            let t = (m as f64 + 0.5) * dt;
+            forward_at(t.clamp(mids[0], mids[n_years - 1])).unwrap()
+        })
+        .collect();
+
+    // Shift each year's step log discounts so they compound back to its one year forward
+    let mut step_forwards = Vec::with_capacity(shape.len());
+    for (steps, forward) in shape.chunks(steps_per_year).zip(forward_curve.iter()) {
+        let logs: Vec<f64> = steps
+            .iter()
+            .map(|rate| compounding.discount_factor(*rate, dt).ln())
+            .collect();
+        let year_log = compounding.discount_factor(*forward, 1.0).ln();
+        if !year_log.is_finite() || logs.iter().any(|log| !log.is_finite()) {
+            return Err(PyValueError::new_err(
+                "Forward rates must give positive discount factors",
+            ));
+        }
+        let shift = (year_log - logs.iter().sum::<f64>()) / steps_per_year as f64;
+        for log in logs {
+            step_forwards.push(compounding.implied_rate((log + shift).exp(), dt)?);
+        }
+    }
+
+    Ok(step_forwards)
+}
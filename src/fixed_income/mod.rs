@@ -2,15 +2,29 @@
 
 use pyo3::prelude::*;
 
+mod batch_pricing;
+mod bdt;
+mod bond_curve_fitter;
+mod exercise;
+mod fixed_rate_bond;
+mod hull_white;
+mod key_rate;
+mod lattice;
 mod option_embedded_bond;
 mod simple_bond;
+mod vol_calibration;
 
 
 #[pymodule]
 pub fn register_fixed_income(py: Python, parent_m: &PyModule) -> PyResult<()> {
     let fixed_income = PyModule::new(py, "fixed_income")?;
     fixed_income.add_class::<simple_bond::SimpleBond>()?;
+    fixed_income.add_class::<fixed_rate_bond::FixedRateBond>()?;
     fixed_income.add_class::<option_embedded_bond::OptionEmbeddedBond>()?;
+    fixed_income.add_class::<bond_curve_fitter::BondCurveFitter>()?;
+    fixed_income.add_class::<key_rate::KeyRateRisk>()?;
+    fixed_income.add_class::<vol_calibration::TreeVolCalibration>()?;
+    fixed_income.add_function(wrap_pyfunction!(batch_pricing::price_bonds, fixed_income)?)?;
     parent_m.add_submodule(fixed_income)?;
 
     Ok(())
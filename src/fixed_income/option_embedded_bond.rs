@@ -1,15 +1,95 @@
 /*
-Calculates price of an option embedded bond using a binomial tree
-- All rates stated as continously compounded
+Calculates price of an option embedded bond using a binomial or trinomial tree
+- All rates stated as continously compounded unless a compounding convention is given
 
 [TODO]
 - add coupon payment scheme to structure
 */
 
+use chrono::NaiveDate;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
+use crate::fixed_income::bdt::{self, BdtLevel, BinomialTreeLevel};
+use crate::fixed_income::exercise::{self, ExercisePolicy};
+use crate::fixed_income::hull_white::{self, HullWhiteLevel, TrinomialTreeLevel};
+use crate::fixed_income::lattice::{self, Lattice, Level};
+use crate::interpolate::cubic_spline::CubicSpline;
+use crate::interpolate::linear_spline::LinearSpline;
+use crate::rates::compounding::Compounding;
+use crate::rates::yield_curve::{CurveTime, YieldCurve};
+use crate::solver::root;
 
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Calibration {
+    None,
+    Bdt,
+}
+
+impl Calibration {
+    fn parse(calibration: &str) -> PyResult<Self> {
+        match calibration {
+            "none" => Ok(Calibration::None),
+            "bdt" => Ok(Calibration::Bdt),
+            _ => Err(PyValueError::new_err(
+                "Invalid calibration. Use none or bdt",
+            )),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TreeModel {
+    Binomial,
+    HullWhite,
+}
+
+impl TreeModel {
+    fn parse(model: &str) -> PyResult<Self> {
+        match model {
+            "binomial" => Ok(TreeModel::Binomial),
+            "hull_white" => Ok(TreeModel::HullWhite),
+            _ => Err(PyValueError::new_err(
+                "Invalid tree model. Use binomial or hull_white",
+            )),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OptionType {
+    Call,
+    Put,
+    CallPut,
+}
+
+impl OptionType {
+    fn parse(bond_option: &str) -> PyResult<Self> {
+        match bond_option {
+            "call" => Ok(OptionType::Call),
+            "put" => Ok(OptionType::Put),
+            "call_put" => Ok(OptionType::CallPut),
+            _ => Err(PyValueError::new_err(
+                "Invalid option type parameter. Use call, put or call_put",
+            )),
+        }
+    }
+}
+
+
+// Vol term structure, a spline in years or one vol per step, replacing the flat interest_vol
+#[derive(Clone, FromPyObject)]
+pub enum VolCurve {
+    Linear(LinearSpline),
+    Cubic(CubicSpline),
+    Steps(Vec<f64>),
+}
+
+
+#[derive(Clone)]
 #[pyclass]
 pub struct OptionEmbeddedBond {
     #[pyo3(get, set)]
@@ -24,21 +104,346 @@ pub struct OptionEmbeddedBond {
     interest_vol: f64,
     #[pyo3(get, set)]
     coupons: Vec<f64>,
+    #[pyo3(get, set)]
+    compounding: Compounding,
+    #[pyo3(get, set)]
+    calibration: String,
+    #[pyo3(get)]
+    calibration_errors: Vec<f64>,
+    #[pyo3(get, set)]
+    model: String,
+    #[pyo3(get, set)]
+    mean_reversion: f64,
+    #[pyo3(get, set)]
+    steps_per_year: usize,
+    #[pyo3(get, set)]
+    interpolation: String,
+    #[pyo3(get)]
+    dt: f64,
+    #[pyo3(get)]
+    step_forwards: Vec<f64>,
+    #[pyo3(get)]
+    step_coupons: Vec<f64>,
+    #[pyo3(get)]
+    step_vols: Vec<f64>,
+    #[pyo3(get)]
+    exercise_schedule: Vec<(f64, f64, String)>,
+    #[pyo3(get, set)]
+    european: bool,
+    #[pyo3(get, set)]
+    put_price: Option<f64>,
+    #[pyo3(get)]
+    put_schedule: Vec<(f64, f64, String)>,
+    #[pyo3(get)]
+    call_strikes: Vec<Option<f64>>,
+    #[pyo3(get)]
+    put_strikes: Vec<Option<f64>>,
+    #[pyo3(get, set)]
+    keep_tree: bool,
+    #[pyo3(get, set)]
+    keep_levels: Vec<usize>,
+    #[pyo3(get)]
+    retained_levels: Vec<(usize, Vec<f64>, Vec<f64>)>,
     #[pyo3(get)]
     binomial_tree: Vec<BinomialTreeLevel>,
+    #[pyo3(get)]
+    trinomial_tree: Vec<TrinomialTreeLevel>,
+    #[pyo3(get)]
+    exercise_map: Vec<Vec<bool>>,
+    #[pyo3(get)]
+    call_map: Vec<Vec<bool>>,
+    #[pyo3(get)]
+    put_map: Vec<Vec<bool>>,
+    #[pyo3(get)]
+    exercise_probabilities: Vec<f64>,
+    #[pyo3(get)]
+    expected_life: f64,
+    #[pyo3(get)]
+    price: f64,
+    #[pyo3(get)]
+    straight_value: f64,
+    #[pyo3(get)]
+    option_value: f64,
+    spread: f64,
+    coupon_levels: Vec<usize>,
+    exercise_policies: Vec<ExercisePolicy>,
+    vol_curve: Option<VolCurve>,
+    vol_shift: f64,
 }
 
 #[pymethods]
 impl OptionEmbeddedBond {
+    // Valuation releases the GIL so bonds can be priced from several Python threads
     #[new]
+    #[pyo3(signature = (
+        notional,
+        bond_option,
+        option_price,
+        forward_curve,
+        interest_vol,
+        coupons=None,
+        compounding=None,
+        calibration="none",
+        model="binomial",
+        mean_reversion=0.1,
+        steps_per_year=1,
+        interpolation="linear",
+        schedule=None,
+        european=false,
+        valuation_date=None,
+        put_price=None,
+        put_schedule=None,
+        vol_curve=None,
+        keep_tree=true,
+        keep_levels=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
+        py: Python<'_>,
         notional: f64,
         bond_option: String,
         option_price: f64,
         forward_curve: Vec<f64>,
         interest_vol: f64,
         coupons: Option<Vec<f64>>,
+        compounding: Option<Compounding>,
+        calibration: &str,
+        model: &str,
+        mean_reversion: f64,
+        steps_per_year: usize,
+        interpolation: &str,
+        schedule: Option<Vec<(CurveTime, f64, String)>>,
+        european: bool,
+        valuation_date: Option<NaiveDate>,
+        put_price: Option<f64>,
+        put_schedule: Option<Vec<(CurveTime, f64, String)>>,
+        vol_curve: Option<VolCurve>,
+        keep_tree: bool,
+        keep_levels: Option<Vec<usize>>,
+    ) -> Result<Self, PyErr> {
+        let mut bond = OptionEmbeddedBond::with_terms(
+            notional,
+            bond_option,
+            option_price,
+            forward_curve,
+            interest_vol,
+            coupons,
+            compounding,
+            calibration,
+            model,
+            mean_reversion,
+            steps_per_year,
+            interpolation,
+            exercise::parse_schedule(schedule, valuation_date)?,
+            european,
+            put_price,
+            exercise::parse_schedule(put_schedule, valuation_date)?,
+            vol_curve,
+            keep_tree,
+            keep_levels.unwrap_or_default(),
+        )?;
+        py.allow_threads(|| bond.value())?;
+
+        Ok(bond)
+    }
+
+    // Build the forward curve for the tree from one year forwards on a yield curve
+    #[staticmethod]
+    #[pyo3(signature = (
+        notional,
+        bond_option,
+        option_price,
+        curve,
+        n_periods,
+        interest_vol,
+        coupons=None,
+        compounding=None,
+        calibration="none",
+        model="binomial",
+        mean_reversion=0.1,
+        steps_per_year=1,
+        interpolation="linear",
+        schedule=None,
+        european=false,
+        put_price=None,
+        put_schedule=None,
+        vol_curve=None,
+        keep_tree=true,
+        keep_levels=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn from_curve(
+        py: Python<'_>,
+        notional: f64,
+        bond_option: String,
+        option_price: f64,
+        curve: &YieldCurve,
+        n_periods: usize,
+        interest_vol: f64,
+        coupons: Option<Vec<f64>>,
+        compounding: Option<Compounding>,
+        calibration: &str,
+        model: &str,
+        mean_reversion: f64,
+        steps_per_year: usize,
+        interpolation: &str,
+        schedule: Option<Vec<(CurveTime, f64, String)>>,
+        european: bool,
+        put_price: Option<f64>,
+        put_schedule: Option<Vec<(CurveTime, f64, String)>>,
+        vol_curve: Option<VolCurve>,
+        keep_tree: bool,
+        keep_levels: Option<Vec<usize>>,
     ) -> Result<Self, PyErr> {
+        let forward_curve = curve.forward_curve(n_periods, 1.0, compounding)?;
+        OptionEmbeddedBond::new(
+            py,
+            notional,
+            bond_option,
+            option_price,
+            forward_curve,
+            interest_vol,
+            coupons,
+            compounding,
+            calibration,
+            model,
+            mean_reversion,
+            steps_per_year,
+            interpolation,
+            schedule,
+            european,
+            curve.reference_date(),
+            put_price,
+            put_schedule,
+            vol_curve,
+            keep_tree,
+            keep_levels,
+        )
+    }
+
+    // Reprice on one year forwards from a curve, keeping the bond terms
+    pub fn price_from_curve(&self, curve: &YieldCurve) -> PyResult<f64> {
+        let forward_curve =
+            curve.forward_curve(self.forward_curve.len(), 1.0, Some(self.compounding))?;
+        self.reprice(forward_curve, self.vol_shift, self.spread)
+    }
+
+    // Vol curve as given, None when the flat interest_vol is used
+    #[getter]
+    fn vol_curve(&self, py: Python<'_>) -> PyObject {
+        match &self.vol_curve {
+            Some(VolCurve::Linear(spline)) => spline.clone().into_py(py),
+            Some(VolCurve::Cubic(spline)) => spline.clone().into_py(py),
+            Some(VolCurve::Steps(vols)) => vols.clone().into_py(py),
+            None => py.None(),
+        }
+    }
+
+    // Price with a constant spread added to every tree rate before discounting
+    pub fn price_with_spread(&self, spread: f64) -> PyResult<f64> {
+        self.reprice(self.forward_curve.clone(), self.vol_shift, spread)
+    }
+
+    // Option adjusted spread, the constant spread over the tree rates that reproduces a price
+    #[pyo3(signature = (market_price, tol=1e-10, max_iter=100))]
+    pub fn oas(
+        &self,
+        py: Python<'_>,
+        market_price: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<f64> {
+        if market_price <= 0.0 {
+            return Err(PyValueError::new_err("Market price must be positive"));
+        }
+        py.allow_threads(|| self.solve_oas(market_price, tol, max_iter))
+    }
+
+    // Duration from a parallel shift of the forward curve, rebuilding the tree each way
+    #[pyo3(signature = (bump=0.0001))]
+    fn effective_duration(&self, py: Python<'_>, bump: f64) -> PyResult<f64> {
+        let (down, price, up) = py.allow_threads(|| self.shifted_prices(bump))?;
+        Ok((down - up) / (2.0 * bump * price))
+    }
+
+    #[pyo3(signature = (bump=0.0001))]
+    fn effective_convexity(&self, py: Python<'_>, bump: f64) -> PyResult<f64> {
+        let (down, price, up) = py.allow_threads(|| self.shifted_prices(bump))?;
+        Ok((down + up - 2.0 * price) / (bump * bump * price))
+    }
+
+    // Price change for a one point (0.01) parallel rise in the step vols, one sided when a vol is below the bump
+    #[pyo3(signature = (bump=0.001))]
+    fn vega(&self, py: Python<'_>, bump: f64) -> PyResult<f64> {
+        if bump <= 0.0 {
+            return Err(PyValueError::new_err("Vol bump must be positive"));
+        }
+        // Bump up only when a step vol is too small to bump down
+        let min_vol = self
+            .step_vols
+            .iter()
+            .fold(f64::INFINITY, |min, vol| min.min(*vol));
+        let down_shift = if bump < min_vol { bump } else { 0.0 };
+        let (up, down) = py.allow_threads(|| -> PyResult<(f64, f64)> {
+            let up = self.reprice(
+                self.forward_curve.clone(),
+                self.vol_shift + bump,
+                self.spread,
+            )?;
+            let down = self.reprice(
+                self.forward_curve.clone(),
+                self.vol_shift - down_shift,
+                self.spread,
+            )?;
+            Ok((up, down))
+        })?;
+
+        Ok((up - down) / (bump + down_shift) * 0.01)
+    }
+
+    // Prices of the bond at each number of steps per year, to check the tree converges
+    fn convergence_study(&self, py: Python<'_>, steps_per_year: Vec<usize>) -> PyResult<Vec<f64>> {
+        py.allow_threads(|| {
+            steps_per_year
+                .iter()
+                .map(|steps| {
+                    self.with_grid(self.forward_curve.clone(), *steps)?
+                        .value_root()
+                })
+                .collect()
+        })
+    }
+
+    // Rebuild the interest rate tree and value the bond
+    pub fn init(&mut self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.value())
+    }
+}
+
+impl OptionEmbeddedBond {
+    // Bond terms before valuation, schedules already in years
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_terms(
+        notional: f64,
+        bond_option: String,
+        option_price: f64,
+        forward_curve: Vec<f64>,
+        interest_vol: f64,
+        coupons: Option<Vec<f64>>,
+        compounding: Option<Compounding>,
+        calibration: &str,
+        model: &str,
+        mean_reversion: f64,
+        steps_per_year: usize,
+        interpolation: &str,
+        exercise_schedule: Vec<(f64, f64, String)>,
+        european: bool,
+        put_price: Option<f64>,
+        put_schedule: Vec<(f64, f64, String)>,
+        vol_curve: Option<VolCurve>,
+        keep_tree: bool,
+        keep_levels: Vec<usize>,
+    ) -> PyResult<Self> {
         // Check if coupons or create a vec of 0 coupons
         let coupons = match coupons {
             Some(vec) => {
@@ -53,175 +458,460 @@ impl OptionEmbeddedBond {
             None => vec![0.0; forward_curve.len()],
         };
 
-        let mut bond = OptionEmbeddedBond {
+        Ok(OptionEmbeddedBond {
             notional,
             bond_option: bond_option.to_string(),
             option_price,
             forward_curve,
             interest_vol,
             coupons,
+            compounding: compounding.unwrap_or_else(Compounding::continuous),
+            calibration: calibration.to_string(),
+            calibration_errors: Vec::new(),
+            model: model.to_string(),
+            mean_reversion,
+            steps_per_year,
+            interpolation: interpolation.to_string(),
+            dt: 1.0,
+            step_forwards: Vec::new(),
+            step_coupons: Vec::new(),
+            step_vols: Vec::new(),
+            exercise_schedule,
+            european,
+            put_price,
+            put_schedule,
+            call_strikes: Vec::new(),
+            put_strikes: Vec::new(),
+            keep_tree,
+            keep_levels,
+            retained_levels: Vec::new(),
             binomial_tree: Vec::new(),
-        };
-        bond.init()?;
-
-        Ok(bond)
+            trinomial_tree: Vec::new(),
+            exercise_map: Vec::new(),
+            call_map: Vec::new(),
+            put_map: Vec::new(),
+            exercise_probabilities: Vec::new(),
+            expected_life: 0.0,
+            price: 0.0,
+            straight_value: 0.0,
+            option_value: 0.0,
+            spread: 0.0,
+            coupon_levels: Vec::new(),
+            exercise_policies: Vec::new(),
+            vol_curve,
+            vol_shift: 0.0,
+        })
     }
 
-    // Initialize the interest rate tree and value the bond
-    pub fn init(&mut self) -> Result<(), PyErr> {
-        for n in 0..self.forward_curve.len() {
-            let nodes = n;
-            let level = BinomialTreeLevel::new(
-                nodes,
-                self.forward_curve[n],
-                self.interest_vol,
-                self.coupons[n],
-            );
-            self.binomial_tree.push(level);
+    // Build the lattice for the model and value the bond
+    fn value(&mut self) -> PyResult<()> {
+        self.binomial_tree.clear();
+        self.trinomial_tree.clear();
+        self.build_steps()?;
+        match TreeModel::parse(&self.model)? {
+            TreeModel::Binomial => {
+                let levels = self.bdt_levels()?;
+                let values = self.value_lattice(&levels);
+                self.binomial_tree = levels
+                    .iter()
+                    .zip(self.step_coupons.iter())
+                    .zip(values)
+                    .map(|((level, coupon), prices)| level.expand(*coupon, prices))
+                    .collect();
+            }
+            TreeModel::HullWhite => {
+                let levels = self.hull_white_levels()?;
+                let values = self.value_lattice(&levels);
+                self.trinomial_tree = levels
+                    .iter()
+                    .zip(self.step_coupons.iter())
+                    .zip(values)
+                    .map(|((level, coupon), prices)| level.expand(*coupon, prices))
+                    .collect();
+            }
         }
-        self.calculate_tree()?;
 
         Ok(())
     }
 
-    fn calculate_tree(&mut self) -> Result<(), PyErr> {
-        match self.bond_option.as_str() {
-            "call" => {
-                let n = self.binomial_tree.len() - 1;
-                self.binomial_tree[n].calculate_last_layer_call(self.notional, self.option_price);
+    // Build the lattice for the model and value the bond at the root alone
+    fn value_root(&mut self) -> PyResult<f64> {
+        self.build_steps()?;
+        match TreeModel::parse(&self.model)? {
+            TreeModel::Binomial => Ok(self.root_price(&self.bdt_levels()?, self.spread)),
+            TreeModel::HullWhite => Ok(self.root_price(&self.hull_white_levels()?, self.spread)),
+        }
+    }
 
-                let mut left_i = self.binomial_tree.len() - 1;
-                while left_i > 0 {
-                    let (left, right) = self.binomial_tree.split_at_mut(left_i);
-                    left[left.len() - 1]
-                        .calculate_branch_node_call(self.option_price, &right[0].prices);
+    // Step forwards, coupons, vols and exercise policies the lattice is built and valued on
+    fn build_steps(&mut self) -> PyResult<()> {
+        self.build_step_grid()?;
+        self.build_step_vols()?;
+        self.build_exercise_policies()
+    }
 
-                    left_i -= 1;
-                }
+    // Unvalued bond with the same terms, shifts and spread on another forward curve and grid
+    fn with_grid(&self, forward_curve: Vec<f64>, steps_per_year: usize) -> PyResult<Self> {
+        let mut bond = OptionEmbeddedBond::with_terms(
+            self.notional,
+            self.bond_option.clone(),
+            self.option_price,
+            forward_curve,
+            self.interest_vol,
+            Some(self.coupons.clone()),
+            Some(self.compounding),
+            &self.calibration,
+            &self.model,
+            self.mean_reversion,
+            steps_per_year,
+            &self.interpolation,
+            self.exercise_schedule.clone(),
+            self.european,
+            self.put_price,
+            self.put_schedule.clone(),
+            self.vol_curve.clone(),
+            false,
+            Vec::new(),
+        )?;
+        bond.vol_shift = self.vol_shift;
+        bond.spread = self.spread;
 
-                Ok(())
-            }
-            "put" => {
-                let n = self.binomial_tree.len() - 1;
-                self.binomial_tree[n].calculate_last_layer_put(self.notional, self.option_price);
+        Ok(bond)
+    }
 
-                let mut left_i = self.binomial_tree.len() - 1;
-                while left_i > 0 {
-                    let (left, right) = self.binomial_tree.split_at_mut(left_i);
-                    left[left.len() - 1]
-                        .calculate_branch_node_put(self.option_price, &right[0].prices);
+    // Root price of the same bond on another forward curve, vol shift or spread
+    fn reprice(&self, forward_curve: Vec<f64>, vol_shift: f64, spread: f64) -> PyResult<f64> {
+        let mut bond = self.with_grid(forward_curve, self.steps_per_year)?;
+        bond.vol_shift = vol_shift;
+        bond.spread = spread;
+        bond.value_root()
+    }
 
-                    left_i -= 1;
-                }
+    // Step forwards and coupons on a grid of steps_per_year steps a year
+    // One year forwards are interpolated onto the steps and shifted in log discount so each year's
+    // steps compound back to its forward; each coupon is paid at its nearest step
+    fn build_step_grid(&mut self) -> PyResult<()> {
+        if self.steps_per_year == 0 {
+            return Err(PyValueError::new_err("Steps per year must be positive"));
+        }
+        if self.forward_curve.is_empty() {
+            return Err(PyValueError::new_err("Forward curve must not be empty"));
+        }
+        if self.coupons.len() != self.forward_curve.len() {
+            return Err(PyValueError::new_err(
+                "Coupon vector length does not match forward curve length",
+            ));
+        }
+
+        let n_years = self.forward_curve.len();
+        self.dt = 1.0 / self.steps_per_year as f64;
+        let n_steps = n_years * self.steps_per_year;
+        self.step_forwards = lattice::step_forwards(
+            &self.forward_curve,
+            self.steps_per_year,
+            &self.interpolation,
+            &self.compounding,
+        )?;
 
-                Ok(())
+        // Coupon k is paid at the end of year k + 1, the end of step m and so on level m + 1
+        self.step_coupons = vec![0.0; n_steps];
+        self.coupon_levels = Vec::with_capacity(n_years);
+        for (k, coupon) in self.coupons.iter().enumerate() {
+            let m = (((k + 1) as f64 / self.dt).round() as usize).clamp(1, n_steps) - 1;
+            self.step_coupons[m] += coupon;
+            self.coupon_levels.push(m + 1);
+        }
+
+        Ok(())
+    }
+
+    // Vol on each step, flat at interest_vol without a vol curve
+    // A given vol curve takes precedence, step_vols always hold the vols that priced the bond
+    fn build_step_vols(&mut self) -> PyResult<()> {
+        let n_steps = self.step_forwards.len();
+        let at_steps =
+            |vol_at: &dyn Fn(f64) -> Option<f64>, (start, end): (f64, f64)| -> Vec<f64> {
+                (0..n_steps)
+                    .map(|n| vol_at((n as f64 * self.dt).clamp(start, end)).unwrap())
+                    .collect()
+            };
+        let vols = match &self.vol_curve {
+            None => vec![self.interest_vol; n_steps],
+            Some(VolCurve::Steps(vols)) => {
+                if vols.len() != n_steps {
+                    return Err(PyValueError::new_err(
+                        "Vol curve length does not match the number of steps",
+                    ));
+                }
+                vols.clone()
             }
-            _ => Err(PyValueError::new_err(
-                "Invalid option type parameter. Use call or put",
-            )),
+            Some(VolCurve::Linear(spline)) => at_steps(&|t| spline.value(t), spline.range()),
+            Some(VolCurve::Cubic(spline)) => at_steps(&|t| spline.value(t), spline.range()),
+        };
+        self.step_vols = vols.iter().map(|vol| vol + self.vol_shift).collect();
+        if self.step_vols.iter().any(|vol| *vol < 0.0) {
+            return Err(PyValueError::new_err("Vols must not be negative"));
         }
+
+        Ok(())
     }
-}
 
+    // Coupon accrued on each step since the last coupon date, zero on coupon dates
+    fn step_accrued(&self) -> Vec<f64> {
+        let mut accrued = vec![0.0; self.step_forwards.len()];
+        let mut last = 0;
+        for &level in &self.coupon_levels {
+            if level <= last {
+                continue;
+            }
+            let coupon = self.step_coupons[level - 1];
+            for (n, value) in accrued.iter_mut().enumerate().take(level).skip(last + 1) {
+                *value = coupon * (n - last) as f64 / (level - last) as f64;
+            }
+            last = level;
+        }
 
-#[derive(Clone)]
-#[pyclass]
-struct BinomialTreeLevel {
-    #[pyo3(get)]
-    prices: Vec<f64>,
-    #[pyo3(get)]
-    rates: Vec<f64>,
-    #[pyo3(get)]
-    coupon: f64,
-}
+        accrued
+    }
 
-impl BinomialTreeLevel {
-    pub fn new(n: usize, rate: f64, interest_vol: f64, coupon: f64) -> Self {
-        let mut level = BinomialTreeLevel {
-            prices: vec![0.0; n + 1], // init vector of prices with zero
-            rates: vec![rate; n + 1], // init with base interest rate, map vol
-            coupon,
+    // Call and put strikes at each step, None where that side cannot be exercised
+    // Without a schedule option_price applies on each coupon date but maturity; a call_put bond is
+    // callable at option_price and putable at put_price, each side on its own schedule
+    // Strikes are clean prices, the policies add accrued so they bound the dirty node values
+    fn build_exercise_policies(&mut self) -> PyResult<()> {
+        let n_steps = self.step_forwards.len();
+        let strikes = |schedule: &[(f64, f64, String)], price: f64| {
+            exercise::step_strikes(
+                schedule,
+                price,
+                self.european,
+                &self.coupon_levels,
+                n_steps,
+                self.dt,
+            )
         };
-        level.calibrate_interest_paths(interest_vol);
-
-        level
-    }
-
-    // Calculate interest rate paths from interest rate volatility
-    fn calibrate_interest_paths(&mut self, interest_vol: f64) {
-        let n = self.rates.len() as f64;
-        self.rates
-            .iter_mut()
-            .enumerate()
-            .for_each(|(index, value)| {
-                let i = index as f64;
-                *value = *value * (interest_vol * (-2.0 * i + n - 1.0)).exp();
-            });
-    }
-
-    // Calculate the PV's at last layer
-    fn calculate_last_layer_call(&mut self, notional: f64, option_price: f64) {
-        let n = self.prices.len();
-        for i in 0..n {
-            let price = (notional + self.coupon) * (-1.0 * self.rates[i]).exp();
-            if price >= option_price {
-                self.prices[i] = option_price;
-            } else {
-                self.prices[i] = price;
+        let (call_strikes, put_strikes) = match OptionType::parse(&self.bond_option)? {
+            OptionType::Call => (
+                strikes(&self.exercise_schedule, self.option_price)?,
+                vec![None; n_steps],
+            ),
+            OptionType::Put => (
+                vec![None; n_steps],
+                strikes(&self.exercise_schedule, self.option_price)?,
+            ),
+            OptionType::CallPut => {
+                let put_price = self.put_price.ok_or_else(|| {
+                    PyValueError::new_err("Callable and putable bonds need a put price")
+                })?;
+                (
+                    strikes(&self.exercise_schedule, self.option_price)?,
+                    strikes(&self.put_schedule, put_price)?,
+                )
             }
+        };
+        self.exercise_policies = call_strikes
+            .iter()
+            .zip(put_strikes.iter())
+            .zip(self.step_accrued())
+            .map(|((call, put), accrued)| ExercisePolicy {
+                call: call.map(|price| price + accrued),
+                put: put.map(|price| price + accrued),
+            })
+            .collect();
+        self.call_strikes = call_strikes;
+        self.put_strikes = put_strikes;
+
+        Ok(())
+    }
+
+    // Discount factor to the end of each step, at each year end the product of the one year forward discounts
+    fn curve_discount_factors(&self) -> Vec<f64> {
+        self.step_forwards
+            .iter()
+            .scan(1.0, |df, rate| {
+                *df *= self.compounding.discount_factor(*rate, self.dt);
+                Some(*df)
+            })
+            .collect()
+    }
+
+    // BDT levels at the step forwards, or with BDT calibration each median rate solved from
+    // Arrow-Debreu state prices so the tree reprices the zero coupon bonds of the curve
+    fn bdt_levels(&self) -> PyResult<Vec<BdtLevel>> {
+        match Calibration::parse(&self.calibration)? {
+            Calibration::None => bdt::levels(&self.step_forwards, &self.step_vols, self.dt),
+            Calibration::Bdt => bdt::build_levels(
+                &self.step_forwards,
+                &self.step_vols,
+                self.dt,
+                &self.compounding,
+            ),
         }
     }
 
-    // Calculate probablilty weighted price with interest rate limit
-    pub fn calculate_branch_node_call(&mut self, option_price: f64, prices: &Vec<f64>) {
-        let n = self.prices.len();
-        for (index, price) in self.prices.iter_mut().enumerate() {
-            let p = (prices[index] * 0.5 + prices[index + 1] * 0.5 + self.coupon)
-                * (-1.0 * self.rates[index]).exp();
+    // Hull-White levels always fitted to the step forwards, with mean reversion and the flat
+    // interest_vol as a normal sigma; vol curves need the binomial model
+    fn hull_white_levels(&self) -> PyResult<Vec<HullWhiteLevel>> {
+        if self.vol_curve.is_some() {
+            return Err(PyValueError::new_err("Vol curves need the binomial model"));
+        }
+        hull_white::build_levels(
+            &self.step_forwards,
+            self.mean_reversion,
+            self.step_vols[0],
+            self.dt,
+            &self.compounding,
+        )
+    }
 
-            // If last level, dont apply option
-            if n == 1 {
-                *price = p;
-            } else {
-                if p >= option_price {
-                    *price = option_price;
-                } else {
-                    *price = p;
+    // Value with and without exercise on a fitted lattice, returning the node values when the tree is kept
+    // Option value is price less straight value, negative for a call the holder is short
+    // The tree keeps the exercise map of nodes exercised, split into call and put maps, and the
+    // probability of first exercise on each step; without it only two levels of values are held
+    // at a time and only the (step, rates, values) of the steps in keep_levels are kept
+    fn value_lattice<L: Level>(&mut self, levels: &[L]) -> Vec<Vec<f64>> {
+        // Exercised and straight values roll back together, a call lowers the node value and a put raises it
+        let lattice = Lattice::new(levels, self.dt, self.compounding, self.spread);
+        let (notional, coupons, policies) =
+            (self.notional, &self.step_coupons, &self.exercise_policies);
+        let (keep_tree, keep_levels) = (self.keep_tree, &self.keep_levels);
+        let n = levels.len();
+        let mapped = if keep_tree { n } else { 0 };
+        let (mut call_map, mut put_map) = (vec![Vec::new(); mapped], vec![Vec::new(); mapped]);
+        let (mut values, mut kept) = (Vec::new(), Vec::new());
+        let (price, straight) = lattice.roll_back_in_place(
+            n,
+            |_| notional,
+            |k, _| coupons[k],
+            |k, _, value| {
+                let exercised = policies[k].apply(value);
+                if keep_tree {
+                    call_map[k].push(exercised < value);
+                    put_map[k].push(exercised > value);
                 }
-            }
-        }
+                exercised
+            },
+            true,
+            |level, current| {
+                if keep_tree {
+                    values.push(current.to_vec());
+                }
+                if keep_levels.contains(&level) {
+                    kept.push((level, current.to_vec()));
+                }
+            },
+        );
+        values.reverse();
+        kept.reverse();
+
+        // Zero coupon bonds to each step end should match the curve, with the tree kept their
+        // state prices roll forward with the exercise probabilities
+        let fitted = Lattice::new(levels, self.dt, self.compounding, 0.0);
+        let zero_prices = if keep_tree {
+            let exercise_map: Vec<Vec<bool>> = call_map
+                .iter()
+                .zip(put_map.iter())
+                .map(|(called, put)| {
+                    called
+                        .iter()
+                        .zip(put.iter())
+                        .map(|(c, p)| *c || *p)
+                        .collect()
+                })
+                .collect();
+            let (probabilities, zero_prices) =
+                exercise::exercise_probabilities(&fitted, &exercise_map);
+            self.expected_life = exercise::expected_life(&probabilities, self.dt);
+            self.exercise_probabilities = probabilities;
+            self.exercise_map = exercise_map;
+            zero_prices
+        } else {
+            self.exercise_map.clear();
+            self.exercise_probabilities.clear();
+            self.expected_life = f64::NAN;
+            fitted.zero_coupon_prices()
+        };
+        self.calibration_errors = zero_prices
+            .iter()
+            .zip(self.curve_discount_factors().iter())
+            .map(|(price, target)| price - target)
+            .collect();
+        self.call_map = call_map;
+        self.put_map = put_map;
+
+        self.retained_levels = kept
+            .into_iter()
+            .map(|(level, prices)| {
+                let rates = levels[level].rates();
+                (level, rates, prices)
+            })
+            .collect();
+        self.price = price;
+        self.straight_value = straight.unwrap();
+        self.option_value = self.price - self.straight_value;
+
+        values
+    }
+
+    // Root price alone at a spread, without calibration errors, straight value or tree
+    fn root_price<L: Level>(&self, levels: &[L], spread: f64) -> f64 {
+        let lattice = Lattice::new(levels, self.dt, self.compounding, spread);
+        let (notional, coupons, policies) =
+            (self.notional, &self.step_coupons, &self.exercise_policies);
+        let exercise = |k: usize, _, value| policies[k].apply(value);
+        let (price, _) = lattice.roll_back_in_place(
+            levels.len(),
+            |_| notional,
+            |k, _| coupons[k],
+            exercise,
+            false,
+            |_, _| {},
+        );
+
+        price
     }
 
-    // Calculate the PV's at last layer
-    fn calculate_last_layer_put(&mut self, notional: f64, option_price: f64) {
-        let n = self.prices.len();
-        for i in 0..n {
-            let p = (notional + self.coupon) * (-1.0 * self.rates[i]).exp();
-            if p <= option_price {
-                self.prices[i] = option_price;
-            } else {
-                self.prices[i] = p;
+    // Spread where the model price meets the market price
+    pub fn solve_oas(&self, market_price: f64, tol: f64, max_iter: usize) -> PyResult<f64> {
+        // The spread leaves the fitted levels unchanged, so they are built once
+        let mut bond = self.with_grid(self.forward_curve.clone(), self.steps_per_year)?;
+        bond.build_steps()?;
+        match TreeModel::parse(&self.model)? {
+            TreeModel::Binomial => {
+                bond.solve_spread(&bond.bdt_levels()?, market_price, tol, max_iter)
+            }
+            TreeModel::HullWhite => {
+                bond.solve_spread(&bond.hull_white_levels()?, market_price, tol, max_iter)
             }
         }
     }
 
-    // Calculate probability weighted prices with interest rate cap
-    pub fn calculate_branch_node_put(&mut self, option_price: f64, prices: &Vec<f64>) {
-        let n = self.prices.len();
-        for (index, price) in self.prices.iter_mut().enumerate() {
-            let p = (prices[index] * 0.5 + prices[index + 1] * 0.5 + self.coupon)
-                * (-1.0 * self.rates[index]).exp();
+    // Spread on built levels where the root price meets the market price
+    fn solve_spread<L: Level>(
+        &self,
+        levels: &[L],
+        market_price: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<f64> {
+        // Price falls as the spread rises, so bracket then solve with Brent
+        let error = |spread: f64| self.root_price(levels, spread) - market_price;
+        let lower = (self.compounding.min_rate(self.dt) + 1e-8).max(-1.0);
+        let (a, b) = root::bracket(error, -0.01, 0.01, (lower, 1.0))?;
+        root::brent(error, a, b, tol, max_iter)
+    }
 
-            // If last level, dont apply option
-            if n == 1 {
-                *price = p;
-            } else {
-                if p <= option_price {
-                    *price = option_price;
-                } else {
-                    *price = p;
-                }
-            }
+    // Prices with the forward curve shifted down, unchanged and up
+    pub fn shifted_prices(&self, bump: f64) -> PyResult<(f64, f64, f64)> {
+        if bump <= 0.0 {
+            return Err(PyValueError::new_err("Bump size must be positive"));
         }
+        let shift =
+            |size: f64| -> Vec<f64> { self.forward_curve.iter().map(|r| r + size).collect() };
+        let down = self.reprice(shift(-bump), self.vol_shift, self.spread)?;
+        let price = self.reprice(self.forward_curve.clone(), self.vol_shift, self.spread)?;
+        let up = self.reprice(shift(bump), self.vol_shift, self.spread)?;
+
+        Ok((down, price, up))
     }
 }
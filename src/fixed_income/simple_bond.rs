@@ -1,23 +1,33 @@
 /*
 Calcuates the price of a coupon paying bond
+- Interest rate stated with the compounding convention, annual compounding by default
+- Negative yields are supported down to the lowest rate the convention allows
+- Analytics are recalculated whenever a bond term is changed
 */
 
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
+use crate::rates::compounding::Compounding;
+use crate::rates::yield_curve::YieldCurve;
+use crate::solver::root;
+
 
 #[pyclass]
 pub struct SimpleBond {
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     notional: f64,
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     n_period: i32,
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     coupon_amount: f64,
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     coupon_freq: f64,
     #[pyo3(get)]
     interest_rate: f64,
     #[pyo3(get)]
+    compounding: Compounding,
+    #[pyo3(get)]
     price: f64,
     #[pyo3(get)]
     mod_duration: f64,
@@ -25,32 +35,74 @@ pub struct SimpleBond {
     mac_duration: f64,
     #[pyo3(get)]
     convexity: f64,
+    #[pyo3(get)]
+    dv01: f64,
 }
 
 #[pymethods]
 impl SimpleBond {
     #[new]
+    #[pyo3(signature = (notional, n_period, coupon_amount, coupon_freq, interest_rate, compounding=None))]
     pub fn new(
         notional: f64,
         n_period: i32,
         coupon_amount: f64,
         coupon_freq: f64,
         interest_rate: f64,
-    ) -> Self {
+        compounding: Option<Compounding>,
+    ) -> Result<Self, PyErr> {
+        let compounding = match compounding {
+            Some(compounding) => compounding,
+            None => Compounding::periodic(1)?,
+        };
+        check_terms(n_period, coupon_freq, interest_rate, &compounding)?;
+
         let mut simple_bond = SimpleBond {
             notional,
             n_period,
             coupon_amount,
             coupon_freq,
             interest_rate,
+            compounding,
             price: 0.0,
             mod_duration: 0.0,
             mac_duration: 0.0,
             convexity: 0.0,
+            dv01: 0.0,
         };
 
         simple_bond.calculate();
-        simple_bond
+        Ok(simple_bond)
+    }
+
+    #[setter]
+    fn set_notional(&mut self, notional: f64) {
+        self.notional = notional;
+        self.calculate();
+    }
+
+    #[setter]
+    fn set_n_period(&mut self, n_period: i32) -> PyResult<()> {
+        check_terms(n_period, self.coupon_freq, self.interest_rate, &self.compounding)?;
+        self.n_period = n_period;
+        self.calculate();
+
+        Ok(())
+    }
+
+    #[setter]
+    fn set_coupon_amount(&mut self, coupon_amount: f64) {
+        self.coupon_amount = coupon_amount;
+        self.calculate();
+    }
+
+    #[setter]
+    fn set_coupon_freq(&mut self, coupon_freq: f64) -> PyResult<()> {
+        check_terms(self.n_period, coupon_freq, self.interest_rate, &self.compounding)?;
+        self.coupon_freq = coupon_freq;
+        self.calculate();
+
+        Ok(())
     }
 
     pub fn calculate(&mut self) {
@@ -61,63 +113,126 @@ impl SimpleBond {
 
     // Simple bond pricing formula assuming a flat interest rate curve
     fn calc_price(&mut self) {
-        let n_coupons: f64 = (self.n_period as f64) * self.coupon_freq;
-        let eff_rate: f64 = (1.0 + self.interest_rate).powf(1.0 / self.coupon_freq) - 1.0;
-        let pv_coupons = self.coupon_amount * (1.0 - (1.0 + eff_rate).powf(-n_coupons)) / eff_rate;
-
-        let pv_notional = self.notional / (1.0 + self.interest_rate).powi(self.n_period);
-        self.price = pv_coupons + pv_notional;
+        self.price = self.price_at(self.interest_rate);
     }
 
-    // Calculate the mac duration and derive the modified duration
+    // Calculate the mac duration and the modified duration as -dP/dr / P
+    // Under annual compounding modified duration is mac_duration / (1 + r)
     fn calc_dur(&mut self) {
-        let mut numerator: f64 = 0.0;
-        let n_coupons = (self.n_period as f64) * self.coupon_freq;
-        let eff_rate: f64 = (1.0 + self.interest_rate).powf(1.0 / self.coupon_freq) - 1.0;
-        for n in 1..=(n_coupons as i32) {
-            let pv_c = (n as f64) / (self.coupon_freq as f64) * self.coupon_amount
-                / (1.0 + eff_rate).powi(n);
-            numerator += pv_c;
+        let mut mac_numerator: f64 = 0.0;
+        let mut mod_numerator: f64 = 0.0;
+        for (t, cf) in self.cash_flows() {
+            let (df, d_df, _) = self.compounding.discount_derivatives(self.interest_rate, t);
+            mac_numerator += t * cf * df;
+            mod_numerator -= cf * d_df;
         }
 
-        numerator += ((self.n_period as f64) * self.notional)
-            / (1.0 + self.interest_rate).powi(self.n_period);
-
-        self.mac_duration = numerator / self.price;
-        self.mod_duration =
-            self.mac_duration / (1.0 + (self.interest_rate / (self.coupon_freq as f64)));
+        self.mac_duration = mac_numerator / self.price;
+        self.mod_duration = mod_numerator / self.price;
+        self.dv01 = self.mod_duration * self.price * 0.0001;
     }
 
+    // Convexity as the second derivative of price to the rate over price
     fn calc_convex(&mut self) {
-        let notional_term = 1.0 / (self.price * (1.0 + self.interest_rate).powi(2));
-        let mut cf_term = 0.0;
-        for t in 1..self.n_period {
-            let cf = self.coupon_amount * ((t as f64).powi(2) + (t as f64))
-                / (1.0 + self.interest_rate).powi(t);
-            cf_term += cf;
+        let numerator: f64 = self
+            .cash_flows()
+            .iter()
+            .map(|(t, cf)| {
+                let (_, _, d2_df) = self.compounding.discount_derivatives(self.interest_rate, *t);
+                cf * d2_df
+            })
+            .sum();
+
+        self.convexity = numerator / self.price;
+    }
+
+    // Solve the interest rate that reproduces a price
+    #[pyo3(signature = (price, tol=1e-10, max_iter=100))]
+    fn yield_from_price(&self, price: f64, tol: f64, max_iter: usize) -> PyResult<f64> {
+        if price <= 0.0 {
+            return Err(PyValueError::new_err("Price must be positive"));
         }
-        self.convexity = notional_term * cf_term;
+
+        let f = |rate: f64| self.price_at(rate) - price;
+        let df = |rate: f64| {
+            let h = 1e-6;
+            (self.price_at(rate + h) - self.price_at(rate - h)) / (2.0 * h)
+        };
+
+        let lower = (self.compounding.min_rate(self.n_period as f64) + 1e-8).max(-10.0);
+        root::solve(f, df, self.interest_rate, (lower, 10.0), tol, max_iter)
+    }
+
+    // Price by discounting the cash flows on a yield curve instead of a flat rate
+    pub fn price_from_curve(&self, curve: &YieldCurve) -> f64 {
+        self.cash_flows()
+            .iter()
+            .map(|(t, cf)| cf * curve.discount(*t))
+            .sum()
     }
 
     // Create vector of bond prices by interest rates
     fn plot_price_range(&self) -> PyResult<(Vec<f64>, Vec<f64>)> {
-        // Create linear space of interest rate range
-        let n_rates = (self.interest_rate * 2.0 * 100.0 / 0.1).round() as i32;
-        let int_rates_range: Vec<_> = (0..=n_rates).map(|x| (x as f64) * 0.001).collect();
+        // Create linear space of interest rate range around the current rate
+        let spread = self.interest_rate.abs().max(0.01);
+        let n_rates = (spread * 2.0 * 100.0 / 0.1).round() as i32;
+        let int_rates_range: Vec<_> = (0..=n_rates)
+            .map(|x| self.interest_rate - spread + (x as f64) * 0.001)
+            .filter(|rate| *rate > self.compounding.min_rate(self.n_period as f64))
+            .collect();
 
-        let mut price_range: Vec<f64> = Vec::new();
-        for i in &int_rates_range {
-            let n_coupons: f64 = (self.n_period as f64) * self.coupon_freq;
-            let eff_rate: f64 = (1.0 + i).powf(1.0 / self.coupon_freq) - 1.0;
-            let pv_coupons =
-                self.coupon_amount * (1.0 - (1.0 + eff_rate).powf(-n_coupons)) / eff_rate;
+        let price_range: Vec<f64> = int_rates_range.iter().map(|i| self.price_at(*i)).collect();
 
-            let pv_notional = self.notional / (1.0 + i).powi(self.n_period);
-            let price = pv_coupons + pv_notional;
+        Ok((int_rates_range, price_range))
+    }
+}
 
-            price_range.push(price);
-        }
+impl SimpleBond {
+    // Coupon and notional cash flows with their payment times in years
+    pub fn cash_flows(&self) -> Vec<(f64, f64)> {
+        let n_coupons = (self.n_period as f64 * self.coupon_freq).round() as i32;
+        let mut cash_flows: Vec<(f64, f64)> = (1..=n_coupons)
+            .map(|n| (n as f64 / self.coupon_freq, self.coupon_amount))
+            .collect();
+        cash_flows.push((self.n_period as f64, self.notional));
+
+        cash_flows
+    }
 
-        Ok((int_rates_range, price_range))
+    // Discounted cash flows, exact at a zero rate where every discount factor is one
+    fn price_at(&self, rate: f64) -> f64 {
+        self.cash_flows()
+            .iter()
+            .map(|(t, cf)| cf * self.compounding.discount_factor(rate, *t))
+            .sum()
     }
 }
+
+
+// Validate the bond terms before pricing
+fn check_terms(
+    n_period: i32,
+    coupon_freq: f64,
+    interest_rate: f64,
+    compounding: &Compounding,
+) -> Result<(), PyErr> {
+    if n_period <= 0 {
+        return Err(PyValueError::new_err("Number of periods must be positive"));
+    }
+    if coupon_freq <= 0.0 {
+        return Err(PyValueError::new_err("Coupon frequency must be positive"));
+    }
+    let n_coupons = n_period as f64 * coupon_freq;
+    if (n_coupons - n_coupons.round()).abs() > 1e-9 {
+        return Err(PyValueError::new_err(
+            "Number of periods times coupon frequency must be a whole number of coupons",
+        ));
+    }
+    if interest_rate <= compounding.min_rate(n_period as f64) {
+        return Err(PyValueError::new_err(
+            "Interest rate is below the lowest rate allowed by the compounding convention",
+        ));
+    }
+
+    Ok(())
+}
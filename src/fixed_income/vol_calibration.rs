@@ -0,0 +1,435 @@
+/*
+Calibrates the vol of the BDT rate tree to cap, floor and european swaption quotes
+- Quotes are (kind, expiry, tenor, strike, quote) with kind cap, floor, payer or receiver,
+  expiry and tenor in whole years on the annual grid of the forward curve
+- Caps and floors hold annual caplets resetting from expiry to expiry + tenor - 1, each paid a year later
+- Swaptions exercise at expiry into an annual pay swap of tenor years at the strike
+- Prices are per unit notional, black_vol quotes are priced with Black's formula on the tree discount factors
+- The flat model fits one vol, term_structure fits a vol per distinct last reset year,
+  held flat back to the previous reset year and beyond the last one
+- Errors are model less market price, and model less market Black vol in bp for black_vol quotes
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::fixed_income::bdt::{self, BdtLevel};
+use crate::fixed_income::lattice::{self, Lattice};
+use crate::rates::compounding::Compounding;
+use crate::rates::parametric::CurveFit;
+use crate::solver::least_squares::levenberg_marquardt;
+use crate::solver::root;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum QuoteType {
+    Price,
+    BlackVol,
+}
+
+impl QuoteType {
+    fn parse(quote_type: &str) -> PyResult<Self> {
+        match quote_type {
+            "price" => Ok(QuoteType::Price),
+            "black_vol" => Ok(QuoteType::BlackVol),
+            _ => Err(PyValueError::new_err(
+                "Invalid quote type. Use price or black_vol",
+            )),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum VolModel {
+    Flat,
+    TermStructure,
+}
+
+impl VolModel {
+    fn parse(vol_model: &str) -> PyResult<Self> {
+        match vol_model {
+            "flat" => Ok(VolModel::Flat),
+            "term_structure" => Ok(VolModel::TermStructure),
+            _ => Err(PyValueError::new_err(
+                "Invalid vol model. Use flat or term_structure",
+            )),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InstrumentKind {
+    Cap,
+    Floor,
+    Payer,
+    Receiver,
+}
+
+impl InstrumentKind {
+    fn parse(kind: &str) -> PyResult<Self> {
+        match kind {
+            "cap" => Ok(InstrumentKind::Cap),
+            "floor" => Ok(InstrumentKind::Floor),
+            "payer" => Ok(InstrumentKind::Payer),
+            "receiver" => Ok(InstrumentKind::Receiver),
+            _ => Err(PyValueError::new_err(
+                "Invalid instrument. Use cap, floor, payer or receiver",
+            )),
+        }
+    }
+}
+
+
+struct RateOption {
+    kind: InstrumentKind,
+    expiry: usize,
+    tenor: usize,
+    strike: f64,
+}
+
+impl RateOption {
+    // Year of the last rate fixing the option depends on
+    fn last_reset(&self) -> usize {
+        match self.kind {
+            InstrumentKind::Cap | InstrumentKind::Floor => self.expiry + self.tenor - 1,
+            InstrumentKind::Payer | InstrumentKind::Receiver => self.expiry,
+        }
+    }
+
+    // Backward induction on the tree, zero coupon bonds at each exercise node
+    fn tree_price(&self, lattice: &Lattice<BdtLevel>, steps_per_year: usize) -> f64 {
+        let zero = |from: usize, to: usize| -> Vec<f64> {
+            let (from, to) = (from * steps_per_year, to * steps_per_year);
+            lattice
+                .roll_back(to, from, |_| 1.0, |_, _| 0.0, |_, _, value| value)
+                .swap_remove(0)
+        };
+        let at_root = |payoff: Vec<f64>, year: usize| -> f64 {
+            lattice.roll_back(
+                year * steps_per_year,
+                0,
+                |j| payoff[j],
+                |_, _| 0.0,
+                |_, _, value| value,
+            )[0][0]
+        };
+
+        match self.kind {
+            InstrumentKind::Cap | InstrumentKind::Floor => (self.expiry..self.expiry + self.tenor)
+                .map(|reset| {
+                    let payoff = zero(reset, reset + 1)
+                        .iter()
+                        .map(|p| match self.kind {
+                            InstrumentKind::Cap => (1.0 - p * (1.0 + self.strike)).max(0.0),
+                            _ => (p * (1.0 + self.strike) - 1.0).max(0.0),
+                        })
+                        .collect();
+                    at_root(payoff, reset)
+                })
+                .sum(),
+            InstrumentKind::Payer | InstrumentKind::Receiver => {
+                let zeros: Vec<Vec<f64>> = (1..=self.tenor)
+                    .map(|i| zero(self.expiry, self.expiry + i))
+                    .collect();
+                let last = &zeros[self.tenor - 1];
+                let payoff = (0..last.len())
+                    .map(|j| {
+                        let annuity: f64 = zeros.iter().map(|p| p[j]).sum();
+                        let swap = 1.0 - last[j] - self.strike * annuity;
+                        match self.kind {
+                            InstrumentKind::Payer => swap.max(0.0),
+                            _ => (-swap).max(0.0),
+                        }
+                    })
+                    .collect();
+                at_root(payoff, self.expiry)
+            }
+        }
+    }
+
+    // Black's formula on forwards from the annual discount factors
+    fn black_price(&self, discounts: &[f64], vol: f64) -> f64 {
+        match self.kind {
+            InstrumentKind::Cap | InstrumentKind::Floor => (self.expiry..self.expiry + self.tenor)
+                .map(|reset| {
+                    let forward = discounts[reset] / discounts[reset + 1] - 1.0;
+                    let call = self.kind == InstrumentKind::Cap;
+                    discounts[reset + 1] * black(forward, self.strike, vol, reset as f64, call)
+                })
+                .sum(),
+            InstrumentKind::Payer | InstrumentKind::Receiver => {
+                let annuity: f64 = (1..=self.tenor).map(|i| discounts[self.expiry + i]).sum();
+                let swap_rate =
+                    (discounts[self.expiry] - discounts[self.expiry + self.tenor]) / annuity;
+                let call = self.kind == InstrumentKind::Payer;
+                annuity * black(swap_rate, self.strike, vol, self.expiry as f64, call)
+            }
+        }
+    }
+
+    // Black vol that reproduces a price, NaN when no vol does
+    fn implied_vol(&self, discounts: &[f64], price: f64) -> f64 {
+        root::brent(
+            |vol| self.black_price(discounts, vol) - price,
+            1e-6,
+            5.0,
+            1e-12,
+            200,
+        )
+        .unwrap_or(f64::NAN)
+    }
+}
+
+
+#[pyclass]
+pub struct TreeVolCalibration {
+    #[pyo3(get)]
+    quote_type: String,
+    #[pyo3(get)]
+    vol_model: String,
+    #[pyo3(get)]
+    knot_times: Vec<f64>,
+    #[pyo3(get)]
+    vols: Vec<f64>,
+    #[pyo3(get)]
+    step_vols: Vec<f64>,
+    #[pyo3(get)]
+    market_prices: Vec<f64>,
+    #[pyo3(get)]
+    model_prices: Vec<f64>,
+    #[pyo3(get)]
+    price_errors: Vec<f64>,
+    #[pyo3(get)]
+    market_vols: Option<Vec<f64>>,
+    #[pyo3(get)]
+    model_vols: Option<Vec<f64>>,
+    #[pyo3(get)]
+    vol_errors_bp: Option<Vec<f64>>,
+    #[pyo3(get)]
+    fit: CurveFit,
+}
+
+#[pymethods]
+impl TreeVolCalibration {
+    // Step vols fit an OptionEmbeddedBond on the same forward curve and steps_per_year
+    #[new]
+    #[pyo3(signature = (
+        forward_curve,
+        instruments,
+        quote_type="price",
+        vol_model="flat",
+        steps_per_year=1,
+        compounding=None,
+        interpolation="linear",
+        initial_vol=0.2,
+        tol=1e-14,
+        max_iter=100,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        forward_curve: Vec<f64>,
+        instruments: Vec<(String, usize, usize, f64, f64)>,
+        quote_type: &str,
+        vol_model: &str,
+        steps_per_year: usize,
+        compounding: Option<Compounding>,
+        interpolation: &str,
+        initial_vol: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<Self> {
+        let quotes_by = QuoteType::parse(quote_type)?;
+        let model = VolModel::parse(vol_model)?;
+        let compounding = compounding.unwrap_or_else(Compounding::continuous);
+        if instruments.is_empty() {
+            return Err(PyValueError::new_err(
+                "Calibration needs at least one instrument",
+            ));
+        }
+        if steps_per_year == 0 {
+            return Err(PyValueError::new_err("Steps per year must be positive"));
+        }
+        if initial_vol <= 0.0 {
+            return Err(PyValueError::new_err("Initial vol must be positive"));
+        }
+
+        let n_years = forward_curve.len();
+        let mut options = Vec::with_capacity(instruments.len());
+        let mut quotes = Vec::with_capacity(instruments.len());
+        for (kind, expiry, tenor, strike, quote) in instruments.iter() {
+            if *expiry == 0 || *tenor == 0 || expiry + tenor > n_years {
+                return Err(PyValueError::new_err(
+                    "Expiry and tenor must be at least a year and end within the forward curve",
+                ));
+            }
+            if quotes_by == QuoteType::BlackVol && (*strike <= 0.0 || *quote <= 0.0) {
+                return Err(PyValueError::new_err(
+                    "Black vol quotes need positive strikes and vols",
+                ));
+            }
+            options.push(RateOption {
+                kind: InstrumentKind::parse(kind)?,
+                expiry: *expiry,
+                tenor: *tenor,
+                strike: *strike,
+            });
+            quotes.push(*quote);
+        }
+
+        // One vol for the flat model, else one per distinct last reset year
+        let mut knots: Vec<usize> = match model {
+            VolModel::Flat => vec![n_years],
+            VolModel::TermStructure => options.iter().map(|option| option.last_reset()).collect(),
+        };
+        knots.sort_unstable();
+        knots.dedup();
+        let dt = 1.0 / steps_per_year as f64;
+        let n_steps = n_years * steps_per_year;
+        let step_vols = |vols: &[f64]| -> Vec<f64> {
+            (0..n_steps)
+                .map(|m| {
+                    let t = m as f64 * dt;
+                    let k = knots.partition_point(|knot| (*knot as f64) < t - 1e-12);
+                    vols[k.min(vols.len() - 1)]
+                })
+                .collect()
+        };
+        let forwards =
+            lattice::step_forwards(&forward_curve, steps_per_year, interpolation, &compounding)?;
+        let tree = |vols: &[f64]| bdt::build_levels(&forwards, &step_vols(vols), dt, &compounding);
+
+        // Discount factors at each year end come from the fitted tree, whatever the vol
+        let levels = tree(&vec![initial_vol; knots.len()])?;
+        let zero_prices = Lattice::new(&levels, dt, compounding, 0.0).zero_coupon_prices();
+        let discounts: Vec<f64> = (0..=n_years)
+            .map(|year| {
+                if year == 0 {
+                    1.0
+                } else {
+                    zero_prices[year * steps_per_year - 1]
+                }
+            })
+            .collect();
+        let market_prices: Vec<f64> = match quotes_by {
+            QuoteType::Price => quotes.clone(),
+            QuoteType::BlackVol => options
+                .iter()
+                .zip(quotes.iter())
+                .map(|(option, vol)| option.black_price(&discounts, *vol))
+                .collect(),
+        };
+
+        let model_prices = |vols: &[f64]| -> PyResult<Vec<f64>> {
+            let levels = tree(vols)?;
+            let lattice = Lattice::new(&levels, dt, compounding, 0.0);
+            Ok(options
+                .iter()
+                .map(|option| option.tree_price(&lattice, steps_per_year))
+                .collect())
+        };
+        let objective = |vols: &[f64]| -> Vec<f64> {
+            match model_prices(vols) {
+                Ok(prices) => prices
+                    .iter()
+                    .zip(market_prices.iter())
+                    .map(|(m, p)| m - p)
+                    .collect(),
+                Err(_) => vec![f64::NAN; market_prices.len()],
+            }
+        };
+        let initial = vec![initial_vol; knots.len()];
+        let lower = vec![1e-4; knots.len()];
+        let upper = vec![3.0; knots.len()];
+        let result = levenberg_marquardt(objective, &initial, &lower, &upper, tol, max_iter);
+
+        let fitted = model_prices(&result.params)?;
+        let price_errors: Vec<f64> = fitted
+            .iter()
+            .zip(market_prices.iter())
+            .map(|(m, p)| m - p)
+            .collect();
+        let (market_vols, model_vols, vol_errors_bp) = match quotes_by {
+            QuoteType::Price => (None, None, None),
+            QuoteType::BlackVol => {
+                let implied: Vec<f64> = options
+                    .iter()
+                    .zip(fitted.iter())
+                    .map(|(option, price)| option.implied_vol(&discounts, *price))
+                    .collect();
+                let errors = implied
+                    .iter()
+                    .zip(quotes.iter())
+                    .map(|(m, q)| (m - q) * 10000.0)
+                    .collect();
+                (Some(quotes), Some(implied), Some(errors))
+            }
+        };
+
+        Ok(TreeVolCalibration {
+            quote_type: quote_type.to_string(),
+            vol_model: vol_model.to_string(),
+            knot_times: match model {
+                VolModel::Flat => Vec::new(),
+                VolModel::TermStructure => knots.iter().map(|knot| *knot as f64).collect(),
+            },
+            step_vols: step_vols(&result.params),
+            vols: result.params,
+            market_prices,
+            model_prices: fitted,
+            price_errors: price_errors.clone(),
+            market_vols,
+            model_vols,
+            vol_errors_bp,
+            fit: CurveFit::new(price_errors, result.iterations, result.converged),
+        })
+    }
+}
+
+
+// Black's formula on a forward, undiscounted
+fn black(forward: f64, strike: f64, vol: f64, expiry: f64, call: bool) -> f64 {
+    let std_dev = vol * expiry.sqrt();
+    if std_dev <= 0.0 || forward <= 0.0 {
+        let intrinsic = if call {
+            forward - strike
+        } else {
+            strike - forward
+        };
+        return intrinsic.max(0.0);
+    }
+    let d1 = ((forward / strike).ln() + 0.5 * std_dev * std_dev) / std_dev;
+    let d2 = d1 - std_dev;
+    if call {
+        forward * norm_cdf(d1) - strike * norm_cdf(d2)
+    } else {
+        strike * norm_cdf(-d2) - forward * norm_cdf(-d1)
+    }
+}
+
+
+// Standard normal distribution function from a Chebyshev fit of erfc, relative error below 1.2e-7
+fn norm_cdf(x: f64) -> f64 {
+    const COEFFS: [f64; 10] = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ];
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = COEFFS.iter().rev().fold(0.0, |acc, c| acc * t + c);
+    let erfc = t * (poly - z * z).exp();
+    if x >= 0.0 {
+        1.0 - 0.5 * erfc
+    } else {
+        0.5 * erfc
+    }
+}
@@ -25,6 +25,7 @@ pub struct CubicFn {
 }
 
 
+#[derive(Clone)]
 #[pyclass]
 pub struct CubicSpline {
     #[pyo3(get, set)]
@@ -143,42 +144,13 @@ impl CubicSpline {
 
     // Calculate y's given a vec of x's
     fn get_values(&mut self, x_input: Vec<f64>) -> PyResult<Vec<f64>> {
-        let mut spline_values = Vec::new();
-
-        for value in x_input.iter() {
-            // Binary search of fns to calc y values
-            let mut low = 0;
-            let mut high = self.params.len() - 1;
-
-            if (value < &self.params[0].x_lower)
-                || (value > &self.params[self.params.len() - 1].x_upper)
-            {
-                return Err(PyValueError::new_err("Value not in spline range"));
-            }
-
-            while low <= high {
-                let mid = (high + low) / 2;
-
-                if (value >= &self.params[mid].x_lower) && (value <= &self.params[mid].x_upper) {
-                    let x = value;
-                    let y = self.params[mid].a * x.powi(3)
-                        + self.params[mid].b * x.powi(2)
-                        + self.params[mid].c * x
-                        + &self.params[mid].d;
-
-                    spline_values.push(y);
-                    break;
-                } else if value < &self.params[mid].x_lower {
-                    high = mid - 1;
-                } else if value > &self.params[mid].x_upper {
-                    low = mid + 1;
-                } else {
-                    return Err(PyValueError::new_err("Value error"));
-                }
-            }
-        }
-
-        Ok(spline_values)
+        x_input
+            .iter()
+            .map(|x| {
+                self.value(*x)
+                    .ok_or_else(|| PyValueError::new_err("Value not in spline range"))
+            })
+            .collect()
     }
 
     // Convert the matrix into a vec<vec<>>
@@ -209,3 +181,32 @@ impl CubicSpline {
         Ok(vectors)
     }
 }
+
+impl CubicSpline {
+    // Binary search of fns for the segment containing x, None outside the spline range
+    fn segment(&self, x: f64) -> Option<&CubicFn> {
+        let first = self.params.first()?;
+        let last = self.params.last()?;
+        if x < first.x_lower || x > last.x_upper {
+            return None;
+        }
+
+        let i = self.params.partition_point(|params| params.x_upper < x);
+        self.params.get(i)
+    }
+
+    pub fn value(&self, x: f64) -> Option<f64> {
+        self.segment(x)
+            .map(|params| params.a * x.powi(3) + params.b * x.powi(2) + params.c * x + params.d)
+    }
+
+    pub fn derivative(&self, x: f64) -> Option<f64> {
+        self.segment(x)
+            .map(|params| 3.0 * params.a * x.powi(2) + 2.0 * params.b * x + params.c)
+    }
+
+    // First and last knot
+    pub fn range(&self) -> (f64, f64) {
+        (self.x[0], self.x[self.x.len() - 1])
+    }
+}
@@ -16,6 +16,7 @@ pub struct LinearFn {
 }
 
 
+#[derive(Clone)]
 #[pyclass]
 pub struct LinearSpline {
     #[pyo3(get, set)]
@@ -66,38 +67,40 @@ impl LinearSpline {
     }
 
     pub fn get_values(&mut self, x_input: Vec<f64>) -> PyResult<Vec<f64>> {
-        let mut spline_values: Vec<f64> = Vec::new();
-
-        for value in x_input.iter() {
-            // Binary search of coefficients
-            let mut low: usize = 0;
-            let mut high: usize = self.params.len() - 1;
-
-            if (value < &self.params[0].x_lower)
-                || (value > &self.params[self.params.len() - 1].x_upper)
-            {
-                return Err(PyValueError::new_err("Value not in spline range"));
-            }
-
-            while low <= high {
-                let mid: usize = (high + low) / 2;
-
-                if (value >= &self.params[mid].x_lower) && (value <= &self.params[mid].x_upper) {
-                    spline_values.push(
-                        self.params[mid].b0
-                            + ((*value - self.params[mid].x_lower) * self.params[mid].b1),
-                    );
-                    break;
-                } else if value < &self.params[mid].x_lower {
-                    high = mid - 1;
-                } else if value > &self.params[mid].x_upper {
-                    low = mid + 1;
-                } else {
-                    return Err(PyValueError::new_err("Value error"));
-                }
-            }
+        x_input
+            .iter()
+            .map(|x| {
+                self.value(*x)
+                    .ok_or_else(|| PyValueError::new_err("Value not in spline range"))
+            })
+            .collect()
+    }
+}
+
+impl LinearSpline {
+    // Segment containing x, None outside the spline range
+    fn segment(&self, x: f64) -> Option<&LinearFn> {
+        let first = self.params.first()?;
+        let last = self.params.last()?;
+        if x < first.x_lower || x > last.x_upper {
+            return None;
         }
 
-        Ok(spline_values)
+        let i = self.params.partition_point(|params| params.x_upper < x);
+        self.params.get(i)
+    }
+
+    pub fn value(&self, x: f64) -> Option<f64> {
+        self.segment(x)
+            .map(|params| params.b0 + (x - params.x_lower) * params.b1)
+    }
+
+    pub fn derivative(&self, x: f64) -> Option<f64> {
+        self.segment(x).map(|params| params.b1)
+    }
+
+    // First and last knot
+    pub fn range(&self) -> (f64, f64) {
+        (self.x[0], self.x[self.x.len() - 1])
     }
 }
@@ -2,8 +2,8 @@
 
 use pyo3::prelude::*;
 
-mod cubic_spline;
-mod linear_spline;
+pub mod cubic_spline;
+pub mod linear_spline;
 
 
 #[pymodule]
@@ -1,13 +1,18 @@
 use pyo3::prelude::*;
 
+mod dates;
 mod fixed_income;
 mod interpolate;
+mod rates;
+mod solver;
 
 
 #[pymodule]
 fn rusty_fy(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    dates::register_dates(py, m)?;
     fixed_income::register_fixed_income(py, m)?;
     interpolate::register_interpolate(py, m)?;
+    rates::register_rates(py, m)?;
 
     Ok(())
 }
@@ -0,0 +1,304 @@
+/*
+Bootstraps a yield curve that reprices a set of market instruments
+- Deposits and FRAs are quoted as simple rates
+- Futures are quoted as 100 - rate, less a convexity adjustment to give the forward rate
+- Par swaps are single curve, spot starting, with an annual fixed leg unless given
+- Times are stated in years, pillars are placed at each instrument maturity
+
+Pillars are solved one at a time from the short end. Non local interpolators (cubic)
+move earlier parts of the curve when a later pillar is added, so the pillars are
+re-solved on the full curve until none of them moves by more than the tolerance.
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::rates::compounding::Compounding;
+use crate::rates::yield_curve::YieldCurve;
+use crate::solver::root;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InstrumentType {
+    Deposit,
+    Fra,
+    Future,
+    Swap,
+}
+
+
+#[derive(Clone)]
+#[pyclass]
+pub struct CurveInstrument {
+    #[pyo3(get)]
+    instrument_type: String,
+    #[pyo3(get)]
+    start: f64,
+    #[pyo3(get)]
+    maturity: f64,
+    #[pyo3(get)]
+    quote: f64,
+    #[pyo3(get)]
+    convexity_adjustment: f64,
+    #[pyo3(get)]
+    frequency: u32,
+    kind: InstrumentType,
+}
+
+#[pymethods]
+impl CurveInstrument {
+    #[staticmethod]
+    pub fn deposit(maturity: f64, rate: f64) -> PyResult<Self> {
+        CurveInstrument::build(InstrumentType::Deposit, 0.0, maturity, rate, 0.0, 1)
+    }
+
+    #[staticmethod]
+    pub fn fra(start: f64, end: f64, rate: f64) -> PyResult<Self> {
+        CurveInstrument::build(InstrumentType::Fra, start, end, rate, 0.0, 1)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (start, end, price, convexity_adjustment=0.0))]
+    pub fn future(start: f64, end: f64, price: f64, convexity_adjustment: f64) -> PyResult<Self> {
+        CurveInstrument::build(
+            InstrumentType::Future,
+            start,
+            end,
+            price,
+            convexity_adjustment,
+            1,
+        )
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (maturity, rate, frequency=1))]
+    pub fn swap(maturity: f64, rate: f64, frequency: u32) -> PyResult<Self> {
+        if frequency == 0 {
+            return Err(PyValueError::new_err("Swap fixed frequency must be positive"));
+        }
+        CurveInstrument::build(InstrumentType::Swap, 0.0, maturity, rate, 0.0, frequency)
+    }
+
+    // Market rate implied by the quote
+    #[getter]
+    pub fn market_rate(&self) -> f64 {
+        match self.kind {
+            InstrumentType::Future => (100.0 - self.quote) / 100.0 - self.convexity_adjustment,
+            _ => self.quote,
+        }
+    }
+
+    // Rate implied by a curve, equal to the market rate when the curve reprices it
+    pub fn implied_rate(&self, curve: &YieldCurve) -> f64 {
+        match self.kind {
+            InstrumentType::Deposit | InstrumentType::Fra | InstrumentType::Future => {
+                let tau = self.maturity - self.start;
+                (curve.discount(self.start) / curve.discount(self.maturity) - 1.0) / tau
+            }
+            InstrumentType::Swap => {
+                let freq = self.frequency as f64;
+                let n = (self.maturity * freq).round() as usize;
+                let mut annuity = 0.0;
+                let mut previous = 0.0;
+                for k in 1..=n {
+                    let t = if k == n { self.maturity } else { k as f64 / freq };
+                    annuity += (t - previous) * curve.discount(t);
+                    previous = t;
+                }
+                (1.0 - curve.discount(self.maturity)) / annuity
+            }
+        }
+    }
+}
+
+impl CurveInstrument {
+    fn build(
+        kind: InstrumentType,
+        start: f64,
+        maturity: f64,
+        quote: f64,
+        convexity_adjustment: f64,
+        frequency: u32,
+    ) -> PyResult<Self> {
+        if start < 0.0 || maturity <= start {
+            return Err(PyValueError::new_err(
+                "Instrument maturity must be after a non negative start",
+            ));
+        }
+
+        let instrument_type = match kind {
+            InstrumentType::Deposit => "deposit",
+            InstrumentType::Fra => "fra",
+            InstrumentType::Future => "future",
+            InstrumentType::Swap => "swap",
+        };
+
+        Ok(CurveInstrument {
+            instrument_type: instrument_type.to_string(),
+            start,
+            maturity,
+            quote,
+            convexity_adjustment,
+            frequency,
+            kind,
+        })
+    }
+}
+
+
+#[pyclass]
+pub struct Bootstrapper {
+    #[pyo3(get)]
+    instruments: Vec<CurveInstrument>,
+    #[pyo3(get)]
+    interpolation: String,
+    #[pyo3(get)]
+    variable: String,
+    #[pyo3(get)]
+    curve: YieldCurve,
+    #[pyo3(get)]
+    errors: Vec<f64>,
+    #[pyo3(get)]
+    iterations: usize,
+}
+
+#[pymethods]
+impl Bootstrapper {
+    #[new]
+    #[pyo3(signature = (
+        instruments,
+        interpolation="linear",
+        variable="log_discount",
+        tol=1e-12,
+        max_iter=100,
+    ))]
+    pub fn new(
+        instruments: Vec<CurveInstrument>,
+        interpolation: &str,
+        variable: &str,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<Self, PyErr> {
+        if instruments.is_empty() {
+            return Err(PyValueError::new_err("At least one instrument is required"));
+        }
+
+        let mut instruments = instruments;
+        instruments.sort_by(|a, b| a.maturity.total_cmp(&b.maturity));
+        if instruments.windows(2).any(|w| w[1].maturity - w[0].maturity < 1e-10) {
+            return Err(PyValueError::new_err(
+                "Instruments must have distinct maturities",
+            ));
+        }
+
+        let times: Vec<f64> = instruments.iter().map(|i| i.maturity).collect();
+        let n = times.len();
+        let mut zero_rates = vec![instruments[0].market_rate(); n];
+
+        // Sequential pass, each pillar only sees the curve up to itself
+        for i in 0..n {
+            zero_rates[i] = solve_pillar(
+                &instruments[i],
+                &times[..=i],
+                &zero_rates[..=i],
+                i,
+                interpolation,
+                variable,
+                tol,
+                max_iter,
+            )?;
+        }
+
+        // Global passes on the full curve until the pillars stop moving
+        let mut iterations = 1;
+        while iterations < max_iter {
+            let mut max_change: f64 = 0.0;
+            for i in 0..n {
+                let rate = solve_pillar(
+                    &instruments[i],
+                    &times,
+                    &zero_rates,
+                    i,
+                    interpolation,
+                    variable,
+                    tol,
+                    max_iter,
+                )?;
+                max_change = max_change.max((rate - zero_rates[i]).abs());
+                zero_rates[i] = rate;
+            }
+            iterations += 1;
+            if max_change < tol {
+                break;
+            }
+        }
+
+        let curve = YieldCurve::from_zero_rates(
+            times,
+            zero_rates,
+            interpolation,
+            variable,
+            None,
+            "act/365",
+        )?;
+        let errors = instruments
+            .iter()
+            .map(|instrument| instrument.implied_rate(&curve) - instrument.market_rate())
+            .collect();
+
+        Ok(Bootstrapper {
+            instruments,
+            interpolation: interpolation.to_string(),
+            variable: variable.to_string(),
+            curve,
+            errors,
+            iterations,
+        })
+    }
+
+    // Forward curve input for OptionEmbeddedBond from the bootstrapped curve
+    #[pyo3(signature = (n_steps, dt=1.0, compounding=None))]
+    pub fn forward_curve(
+        &self,
+        n_steps: usize,
+        dt: f64,
+        compounding: Option<Compounding>,
+    ) -> PyResult<Vec<f64>> {
+        self.curve.forward_curve(n_steps, dt, compounding)
+    }
+}
+
+
+// Solve the zero rate at pillar i that reprices its instrument, other pillars held fixed
+#[allow(clippy::too_many_arguments)]
+fn solve_pillar(
+    instrument: &CurveInstrument,
+    times: &[f64],
+    zero_rates: &[f64],
+    i: usize,
+    interpolation: &str,
+    variable: &str,
+    tol: f64,
+    max_iter: usize,
+) -> PyResult<f64> {
+    let target = instrument.market_rate();
+    let error = |rate: f64| -> f64 {
+        let mut rates = zero_rates.to_vec();
+        rates[i] = rate;
+        match YieldCurve::from_zero_rates(
+            times.to_vec(),
+            rates,
+            interpolation,
+            variable,
+            None,
+            "act/365",
+        ) {
+            Ok(curve) => instrument.implied_rate(&curve) - target,
+            Err(_) => f64::NAN,
+        }
+    };
+
+    let guess = zero_rates[i];
+    let (a, b) = root::bracket(error, guess - 0.01, guess + 0.01, (-1.0, 1.0))?;
+    root::brent(error, a, b, tol, max_iter)
+}
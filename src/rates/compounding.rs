@@ -0,0 +1,185 @@
+/*
+Interest rate compounding conventions
+- Simple:                 df = 1 / (1 + r t)
+- Periodic(n):            df = (1 + r / n)^(-n t)
+- Continuous:             df = exp(-r t)
+- Simple then periodic:   simple up to one period (t <= 1 / n), periodic(n) after
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Convention {
+    Simple,
+    Periodic(u32),
+    Continuous,
+    SimpleThenPeriodic(u32),
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[pyclass]
+pub struct Compounding {
+    convention: Convention,
+}
+
+#[pymethods]
+impl Compounding {
+    #[staticmethod]
+    pub fn simple() -> Self {
+        Compounding {
+            convention: Convention::Simple,
+        }
+    }
+
+    #[staticmethod]
+    pub fn periodic(frequency: u32) -> PyResult<Self> {
+        check_frequency(frequency)?;
+        Ok(Compounding {
+            convention: Convention::Periodic(frequency),
+        })
+    }
+
+    #[staticmethod]
+    pub fn continuous() -> Self {
+        Compounding {
+            convention: Convention::Continuous,
+        }
+    }
+
+    #[staticmethod]
+    pub fn simple_then_compounded(frequency: u32) -> PyResult<Self> {
+        check_frequency(frequency)?;
+        Ok(Compounding {
+            convention: Convention::SimpleThenPeriodic(frequency),
+        })
+    }
+
+    #[getter]
+    fn kind(&self) -> &str {
+        match self.convention {
+            Convention::Simple => "simple",
+            Convention::Periodic(_) => "periodic",
+            Convention::Continuous => "continuous",
+            Convention::SimpleThenPeriodic(_) => "simple_then_compounded",
+        }
+    }
+
+    #[getter]
+    fn frequency(&self) -> Option<u32> {
+        match self.convention {
+            Convention::Periodic(n) | Convention::SimpleThenPeriodic(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        match self.frequency() {
+            Some(n) => format!("Compounding.{}({})", self.kind(), n),
+            None => format!("Compounding.{}()", self.kind()),
+        }
+    }
+
+    pub fn discount_factor(&self, rate: f64, t: f64) -> f64 {
+        1.0 / self.compound_factor(rate, t)
+    }
+
+    // Growth of one unit invested at the rate for t years
+    pub fn compound_factor(&self, rate: f64, t: f64) -> f64 {
+        match self.resolve(t) {
+            Convention::Simple => 1.0 + rate * t,
+            Convention::Periodic(n) => {
+                let n = n as f64;
+                (1.0 + rate / n).powf(n * t)
+            }
+            Convention::Continuous => (rate * t).exp(),
+            Convention::SimpleThenPeriodic(_) => unreachable!(),
+        }
+    }
+
+    // Rate under this convention that gives the discount factor over t years
+    pub fn implied_rate(&self, discount_factor: f64, t: f64) -> PyResult<f64> {
+        if discount_factor <= 0.0 || t <= 0.0 {
+            return Err(PyValueError::new_err(
+                "Discount factor and time must be positive",
+            ));
+        }
+
+        let compound = 1.0 / discount_factor;
+        Ok(match self.resolve(t) {
+            Convention::Simple => (compound - 1.0) / t,
+            Convention::Periodic(n) => {
+                let n = n as f64;
+                n * (compound.powf(1.0 / (n * t)) - 1.0)
+            }
+            Convention::Continuous => compound.ln() / t,
+            Convention::SimpleThenPeriodic(_) => unreachable!(),
+        })
+    }
+
+    // Equivalent rate under another convention over the same t years
+    pub fn convert(&self, rate: f64, t: f64, to: &Compounding) -> PyResult<f64> {
+        to.implied_rate(self.discount_factor(rate, t), t)
+    }
+}
+
+impl Compounding {
+    // Simple then periodic resolves to one of its parts depending on t
+    fn resolve(&self, t: f64) -> Convention {
+        match self.convention {
+            Convention::SimpleThenPeriodic(n) => {
+                if t <= 1.0 / n as f64 {
+                    Convention::Simple
+                } else {
+                    Convention::Periodic(n)
+                }
+            }
+            convention => convention,
+        }
+    }
+
+    // Discount factor with its first and second derivatives to the rate
+    pub fn discount_derivatives(&self, rate: f64, t: f64) -> (f64, f64, f64) {
+        match self.resolve(t) {
+            Convention::Simple => {
+                let g = 1.0 + rate * t;
+                (1.0 / g, -t / g.powi(2), 2.0 * t * t / g.powi(3))
+            }
+            Convention::Periodic(n) => {
+                let n = n as f64;
+                let g = 1.0 + rate / n;
+                (
+                    g.powf(-n * t),
+                    -t * g.powf(-n * t - 1.0),
+                    t * (t + 1.0 / n) * g.powf(-n * t - 2.0),
+                )
+            }
+            Convention::Continuous => {
+                let df = (-rate * t).exp();
+                (df, -t * df, t * t * df)
+            }
+            Convention::SimpleThenPeriodic(_) => unreachable!(),
+        }
+    }
+
+    // Lowest rate with a finite positive discount factor at every time up to t
+    pub fn min_rate(&self, t: f64) -> f64 {
+        match self.convention {
+            Convention::Periodic(n) => -(n as f64),
+            Convention::SimpleThenPeriodic(n) => -(n as f64).max(1.0 / t),
+            Convention::Simple => -1.0 / t,
+            Convention::Continuous => f64::NEG_INFINITY,
+        }
+    }
+}
+
+
+fn check_frequency(frequency: u32) -> Result<(), PyErr> {
+    if frequency == 0 {
+        return Err(PyValueError::new_err("Compounding frequency must be positive"));
+    }
+
+    Ok(())
+}
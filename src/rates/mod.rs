@@ -0,0 +1,24 @@
+// Rates Module
+
+use pyo3::prelude::*;
+
+pub mod bootstrap;
+pub mod compounding;
+pub mod parametric;
+pub mod yield_curve;
+
+
+#[pymodule]
+pub fn register_rates(py: Python, parent_m: &PyModule) -> PyResult<()> {
+    let rates = PyModule::new(py, "rates")?;
+    rates.add_class::<bootstrap::Bootstrapper>()?;
+    rates.add_class::<bootstrap::CurveInstrument>()?;
+    rates.add_class::<compounding::Compounding>()?;
+    rates.add_class::<parametric::CurveFit>()?;
+    rates.add_class::<parametric::NelsonSiegel>()?;
+    rates.add_class::<parametric::Svensson>()?;
+    rates.add_class::<yield_curve::YieldCurve>()?;
+    parent_m.add_submodule(rates)?;
+
+    Ok(())
+}
@@ -0,0 +1,619 @@
+/*
+Nelson-Siegel and Svensson parametric curves
+- Zero and forward rates are continuously compounded
+- Parameters are fitted to zero yields or bond prices by bounded Levenberg-Marquardt
+
+Nelson-Siegel forward:  f(t) = b0 + b1 e^(-t/tau) + b2 (t/tau) e^(-t/tau)
+Svensson forward:       adds b3 (t/tau2) e^(-t/tau2) to Nelson-Siegel
+*/
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::rates::compounding::Compounding;
+use crate::rates::yield_curve::YieldCurve;
+use crate::solver::least_squares::levenberg_marquardt;
+use crate::solver::root;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Model {
+    NelsonSiegel,
+    Svensson,
+}
+
+impl Model {
+    pub fn n_params(&self) -> usize {
+        match self {
+            Model::NelsonSiegel => 4,
+            Model::Svensson => 6,
+        }
+    }
+
+    // Parameters ordered b0, b1, b2, tau for Nelson-Siegel and b0, b1, b2, b3, tau1, tau2 for Svensson
+    pub fn zero(&self, params: &[f64], t: f64) -> f64 {
+        match self {
+            Model::NelsonSiegel => {
+                let (slope, hump) = loadings(t, params[3]);
+                params[0] + params[1] * slope + params[2] * hump
+            }
+            Model::Svensson => {
+                let (slope, hump) = loadings(t, params[4]);
+                let (_, hump_2) = loadings(t, params[5]);
+                params[0] + params[1] * slope + params[2] * hump + params[3] * hump_2
+            }
+        }
+    }
+
+    pub fn forward(&self, params: &[f64], t: f64) -> f64 {
+        let decay = |tau: f64| (-t / tau).exp();
+        match self {
+            Model::NelsonSiegel => {
+                let tau = params[3];
+                params[0] + params[1] * decay(tau) + params[2] * (t / tau) * decay(tau)
+            }
+            Model::Svensson => {
+                let (tau_1, tau_2) = (params[4], params[5]);
+                params[0]
+                    + params[1] * decay(tau_1)
+                    + params[2] * (t / tau_1) * decay(tau_1)
+                    + params[3] * (t / tau_2) * decay(tau_2)
+            }
+        }
+    }
+
+    pub fn discount(&self, params: &[f64], t: f64) -> f64 {
+        (-self.zero(params, t) * t).exp()
+    }
+
+    fn default_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        match self {
+            Model::NelsonSiegel => (vec![-0.5, -1.0, -1.0, 0.05], vec![0.5, 1.0, 1.0, 30.0]),
+            Model::Svensson => (
+                vec![-0.5, -1.0, -1.0, -1.0, 0.05, 0.05],
+                vec![0.5, 1.0, 1.0, 1.0, 30.0, 30.0],
+            ),
+        }
+    }
+
+    // Level from the long end and slope from the short end of the data
+    fn initial_guess(&self, short_rate: f64, long_rate: f64) -> Vec<f64> {
+        match self {
+            Model::NelsonSiegel => vec![long_rate, short_rate - long_rate, 0.0, 2.0],
+            Model::Svensson => vec![long_rate, short_rate - long_rate, 0.0, 0.0, 2.0, 5.0],
+        }
+    }
+}
+
+
+// Slope and curvature loadings with their t -> 0 limits
+fn loadings(t: f64, tau: f64) -> (f64, f64) {
+    let x = t / tau;
+    if x < 1e-8 {
+        return (1.0 - x / 2.0, x / 2.0);
+    }
+    let slope = -(-x).exp_m1() / x;
+    (slope, slope - (-x).exp())
+}
+
+
+// Fit diagnostics for a parametric curve
+#[derive(Clone)]
+#[pyclass]
+pub struct CurveFit {
+    #[pyo3(get)]
+    residuals: Vec<f64>,
+    #[pyo3(get)]
+    rmse: f64,
+    #[pyo3(get)]
+    max_error: f64,
+    #[pyo3(get)]
+    iterations: usize,
+    #[pyo3(get)]
+    converged: bool,
+}
+
+impl CurveFit {
+    pub fn new(residuals: Vec<f64>, iterations: usize, converged: bool) -> Self {
+        let n = residuals.len().max(1) as f64;
+        let rmse = (residuals.iter().map(|r| r * r).sum::<f64>() / n).sqrt();
+        let max_error = residuals.iter().fold(0.0_f64, |m, r| m.max(r.abs()));
+
+        CurveFit {
+            residuals,
+            rmse,
+            max_error,
+            iterations,
+            converged,
+        }
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+}
+
+
+// Shared evaluation and fitting for the parametric models
+#[derive(Clone)]
+pub struct ParametricCurve {
+    pub model: Model,
+    pub params: Vec<f64>,
+    pub fit: Option<CurveFit>,
+}
+
+impl ParametricCurve {
+    pub fn new(model: Model, params: Vec<f64>) -> PyResult<Self> {
+        let tau_start = match model {
+            Model::NelsonSiegel => 3,
+            Model::Svensson => 4,
+        };
+        if params[tau_start..].iter().any(|tau| *tau <= 0.0) {
+            return Err(PyValueError::new_err("Decay parameters tau must be positive"));
+        }
+
+        Ok(ParametricCurve {
+            model,
+            params,
+            fit: None,
+        })
+    }
+
+    pub fn zero_rate(&self, t: f64, compounding: Option<Compounding>) -> PyResult<f64> {
+        match compounding {
+            Some(compounding) => compounding.implied_rate(self.discount(t), t),
+            None => Ok(self.model.zero(&self.params, t)),
+        }
+    }
+
+    pub fn forward_rate(&self, t1: f64, t2: f64, compounding: Option<Compounding>) -> PyResult<f64> {
+        if t2 <= t1 {
+            return Err(PyValueError::new_err("Forward end must be after the start"));
+        }
+        let compounding = compounding.unwrap_or_else(Compounding::continuous);
+        compounding.implied_rate(self.discount(t2) / self.discount(t1), t2 - t1)
+    }
+
+    pub fn instantaneous_forward(&self, t: f64) -> f64 {
+        self.model.forward(&self.params, t)
+    }
+
+    pub fn discount(&self, t: f64) -> f64 {
+        self.model.discount(&self.params, t)
+    }
+
+    // Sample the curve on pillar times to use it where a YieldCurve is expected
+    pub fn to_yield_curve(&self, times: Vec<f64>, interpolation: &str) -> PyResult<YieldCurve> {
+        let zero_rates = times.iter().map(|t| self.model.zero(&self.params, *t)).collect();
+        YieldCurve::from_zero_rates(times, zero_rates, interpolation, "zero_rate", None, "act/365")
+    }
+
+    // Fit to zero yields at the given times
+    pub fn fit_yields(
+        model: Model,
+        times: Vec<f64>,
+        yields: Vec<f64>,
+        compounding: Option<Compounding>,
+        bounds: Bounds,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<Self> {
+        if times.len() != yields.len() || times.len() < model.n_params() {
+            return Err(PyValueError::new_err(
+                "Times and yields must have the same length, at least the number of parameters",
+            ));
+        }
+
+        let compounding = compounding.unwrap_or_else(Compounding::continuous);
+        let continuous = Compounding::continuous();
+        let zero_rates = times
+            .iter()
+            .zip(yields.iter())
+            .map(|(t, y)| compounding.convert(*y, *t, &continuous))
+            .collect::<PyResult<Vec<f64>>>()?;
+
+        let objective = |params: &[f64]| -> Vec<f64> {
+            times
+                .iter()
+                .zip(zero_rates.iter())
+                .map(|(t, r)| model.zero(params, *t) - r)
+                .collect()
+        };
+
+        let short_rate = zero_rates[0];
+        let long_rate = zero_rates[zero_rates.len() - 1];
+        ParametricCurve::fit(model, objective, short_rate, long_rate, bounds, tol, max_iter)
+    }
+
+    // Fit to bond prices from each bond's cash flow times and amounts
+    #[allow(clippy::too_many_arguments)]
+    pub fn fit_prices(
+        model: Model,
+        cash_flow_times: Vec<Vec<f64>>,
+        cash_flows: Vec<Vec<f64>>,
+        prices: Vec<f64>,
+        weights: Option<Vec<f64>>,
+        bounds: Bounds,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<Self> {
+        let n = prices.len();
+        if cash_flow_times.len() != n || cash_flows.len() != n {
+            return Err(PyValueError::new_err(
+                "Cash flow times, cash flows and prices must have the same length",
+            ));
+        }
+        if cash_flow_times
+            .iter()
+            .zip(cash_flows.iter())
+            .any(|(t, cf)| t.len() != cf.len() || t.is_empty())
+        {
+            return Err(PyValueError::new_err(
+                "Each bond needs matching, non empty cash flow times and amounts",
+            ));
+        }
+        let weights = weights.unwrap_or_else(|| vec![1.0; n]);
+        if weights.len() != n {
+            return Err(PyValueError::new_err("Weights must have one entry per bond"));
+        }
+        if weights.iter().any(|w| !w.is_finite() || *w <= 0.0) {
+            return Err(PyValueError::new_err("Weights must be finite and positive"));
+        }
+
+        let objective = |params: &[f64]| -> Vec<f64> {
+            (0..n)
+                .map(|i| {
+                    let model_price: f64 = cash_flow_times[i]
+                        .iter()
+                        .zip(cash_flows[i].iter())
+                        .map(|(t, cf)| cf * model.discount(params, *t))
+                        .sum();
+                    weights[i] * (model_price - prices[i])
+                })
+                .collect()
+        };
+
+        // Start from a fit to each bond's flat continuous yield at its maturity
+        let mut maturities = Vec::with_capacity(n);
+        let mut yields = Vec::with_capacity(n);
+        for i in 0..n {
+            let price_error = |y: f64| -> f64 {
+                cash_flow_times[i]
+                    .iter()
+                    .zip(cash_flows[i].iter())
+                    .map(|(t, cf)| cf * (-y * t).exp())
+                    .sum::<f64>()
+                    - prices[i]
+            };
+            let slope = |y: f64| -> f64 {
+                cash_flow_times[i]
+                    .iter()
+                    .zip(cash_flows[i].iter())
+                    .map(|(t, cf)| -t * cf * (-y * t).exp())
+                    .sum()
+            };
+            maturities.push(cash_flow_times[i][cash_flow_times[i].len() - 1]);
+            yields.push(root::solve(price_error, slope, 0.03, (-1.0, 1.0), 1e-12, 100)?);
+        }
+
+        let mut bounds = bounds;
+        if bounds.initial.is_none() && n >= model.n_params() {
+            let start = ParametricCurve::fit_yields(
+                model,
+                maturities.clone(),
+                yields.clone(),
+                None,
+                Bounds {
+                    lower: bounds.lower.clone(),
+                    upper: bounds.upper.clone(),
+                    initial: None,
+                },
+                tol,
+                max_iter,
+            )?;
+            bounds.initial = Some(start.params);
+        }
+
+        let short = (0..n).min_by(|a, b| maturities[*a].total_cmp(&maturities[*b])).unwrap();
+        let long = (0..n).max_by(|a, b| maturities[*a].total_cmp(&maturities[*b])).unwrap();
+        let mut curve = ParametricCurve::fit(
+            model,
+            objective,
+            yields[short],
+            yields[long],
+            bounds,
+            tol,
+            max_iter,
+        )?;
+
+        // Report the unweighted price errors
+        if let Some(fit) = curve.fit.as_mut() {
+            fit.residuals
+                .iter_mut()
+                .zip(weights.iter())
+                .for_each(|(r, w)| *r /= w);
+            *fit = CurveFit::new(fit.residuals.clone(), fit.iterations, fit.converged);
+        }
+
+        Ok(curve)
+    }
+
+    pub fn fit<F>(
+        model: Model,
+        objective: F,
+        short_rate: f64,
+        long_rate: f64,
+        bounds: Bounds,
+        tol: f64,
+        max_iter: usize,
+    ) -> PyResult<Self>
+    where
+        F: Fn(&[f64]) -> Vec<f64>,
+    {
+        let (default_lower, default_upper) = model.default_bounds();
+        let lower = bounds.lower.unwrap_or(default_lower);
+        let upper = bounds.upper.unwrap_or(default_upper);
+        let initial = bounds
+            .initial
+            .unwrap_or_else(|| model.initial_guess(short_rate, long_rate));
+        if lower.len() != model.n_params()
+            || upper.len() != model.n_params()
+            || initial.len() != model.n_params()
+        {
+            return Err(PyValueError::new_err(format!(
+                "Bounds and initial guess need {} parameters",
+                model.n_params()
+            )));
+        }
+        if lower.iter().zip(upper.iter()).any(|(l, u)| l > u) {
+            return Err(PyValueError::new_err("Lower bounds must not exceed upper bounds"));
+        }
+
+        let result = levenberg_marquardt(objective, &initial, &lower, &upper, tol, max_iter);
+        let mut curve = ParametricCurve::new(model, result.params)?;
+        curve.fit = Some(CurveFit::new(
+            result.residuals,
+            result.iterations,
+            result.converged,
+        ));
+
+        Ok(curve)
+    }
+}
+
+
+// Optional parameter bounds and starting point for a fit
+pub struct Bounds {
+    pub lower: Option<Vec<f64>>,
+    pub upper: Option<Vec<f64>>,
+    pub initial: Option<Vec<f64>>,
+}
+
+
+// Python methods shared by the parametric curve classes, the model specific ones are passed in
+macro_rules! parametric_curve_methods {
+    ($name:ident, $model:expr, { $($specific:tt)* }) => {
+        #[pymethods]
+        impl $name {
+            $($specific)*
+
+            // Fit to zero yields, stated as continuously compounded unless given
+            #[staticmethod]
+            #[pyo3(signature = (
+                times,
+                yields,
+                compounding=None,
+                lower=None,
+                upper=None,
+                initial=None,
+                tol=1e-12,
+                max_iter=200,
+            ))]
+            #[allow(clippy::too_many_arguments)]
+            fn fit_yields(
+                times: Vec<f64>,
+                yields: Vec<f64>,
+                compounding: Option<Compounding>,
+                lower: Option<Vec<f64>>,
+                upper: Option<Vec<f64>>,
+                initial: Option<Vec<f64>>,
+                tol: f64,
+                max_iter: usize,
+            ) -> PyResult<Self> {
+                let bounds = Bounds {
+                    lower,
+                    upper,
+                    initial,
+                };
+                Ok($name {
+                    curve: ParametricCurve::fit_yields(
+                        $model,
+                        times,
+                        yields,
+                        compounding,
+                        bounds,
+                        tol,
+                        max_iter,
+                    )?,
+                })
+            }
+
+            // Fit to dirty bond prices from each bond's cash flow times and amounts
+            #[staticmethod]
+            #[pyo3(signature = (
+                cash_flow_times,
+                cash_flows,
+                prices,
+                weights=None,
+                lower=None,
+                upper=None,
+                initial=None,
+                tol=1e-12,
+                max_iter=200,
+            ))]
+            #[allow(clippy::too_many_arguments)]
+            fn fit_prices(
+                cash_flow_times: Vec<Vec<f64>>,
+                cash_flows: Vec<Vec<f64>>,
+                prices: Vec<f64>,
+                weights: Option<Vec<f64>>,
+                lower: Option<Vec<f64>>,
+                upper: Option<Vec<f64>>,
+                initial: Option<Vec<f64>>,
+                tol: f64,
+                max_iter: usize,
+            ) -> PyResult<Self> {
+                let bounds = Bounds {
+                    lower,
+                    upper,
+                    initial,
+                };
+                Ok($name {
+                    curve: ParametricCurve::fit_prices(
+                        $model,
+                        cash_flow_times,
+                        cash_flows,
+                        prices,
+                        weights,
+                        bounds,
+                        tol,
+                        max_iter,
+                    )?,
+                })
+            }
+
+            #[getter]
+            fn params(&self) -> Vec<f64> {
+                self.curve.params.clone()
+            }
+
+            #[getter]
+            fn fit(&self) -> Option<CurveFit> {
+                self.curve.fit.clone()
+            }
+
+            #[pyo3(signature = (t, compounding=None))]
+            fn zero_rate(&self, t: f64, compounding: Option<Compounding>) -> PyResult<f64> {
+                self.curve.zero_rate(t, compounding)
+            }
+
+            #[pyo3(signature = (t1, t2, compounding=None))]
+            fn forward_rate(&self, t1: f64, t2: f64, compounding: Option<Compounding>) -> PyResult<f64> {
+                self.curve.forward_rate(t1, t2, compounding)
+            }
+
+            fn instantaneous_forward(&self, t: f64) -> f64 {
+                self.curve.instantaneous_forward(t)
+            }
+
+            fn discount_factor(&self, t: f64) -> f64 {
+                self.curve.discount(t)
+            }
+
+            #[pyo3(signature = (times, interpolation="cubic"))]
+            fn to_yield_curve(&self, times: Vec<f64>, interpolation: &str) -> PyResult<YieldCurve> {
+                self.curve.to_yield_curve(times, interpolation)
+            }
+        }
+
+        impl $name {
+            pub fn from_curve(curve: ParametricCurve) -> Self {
+                $name { curve }
+            }
+        }
+    };
+}
+
+
+#[pyclass]
+pub struct NelsonSiegel {
+    curve: ParametricCurve,
+}
+
+parametric_curve_methods!(NelsonSiegel, Model::NelsonSiegel, {
+    #[new]
+    pub fn new(beta0: f64, beta1: f64, beta2: f64, tau: f64) -> PyResult<Self> {
+        Ok(NelsonSiegel {
+            curve: ParametricCurve::new(Model::NelsonSiegel, vec![beta0, beta1, beta2, tau])?,
+        })
+    }
+
+    #[getter]
+    fn beta0(&self) -> f64 {
+        self.curve.params[0]
+    }
+
+    #[getter]
+    fn beta1(&self) -> f64 {
+        self.curve.params[1]
+    }
+
+    #[getter]
+    fn beta2(&self) -> f64 {
+        self.curve.params[2]
+    }
+
+    #[getter]
+    fn tau(&self) -> f64 {
+        self.curve.params[3]
+    }
+});
+
+
+#[pyclass]
+pub struct Svensson {
+    curve: ParametricCurve,
+}
+
+parametric_curve_methods!(Svensson, Model::Svensson, {
+    #[new]
+    pub fn new(
+        beta0: f64,
+        beta1: f64,
+        beta2: f64,
+        beta3: f64,
+        tau1: f64,
+        tau2: f64,
+    ) -> PyResult<Self> {
+        Ok(Svensson {
+            curve: ParametricCurve::new(
+                Model::Svensson,
+                vec![beta0, beta1, beta2, beta3, tau1, tau2],
+            )?,
+        })
+    }
+
+    #[getter]
+    fn beta0(&self) -> f64 {
+        self.curve.params[0]
+    }
+
+    #[getter]
+    fn beta1(&self) -> f64 {
+        self.curve.params[1]
+    }
+
+    #[getter]
+    fn beta2(&self) -> f64 {
+        self.curve.params[2]
+    }
+
+    #[getter]
+    fn beta3(&self) -> f64 {
+        self.curve.params[3]
+    }
+
+    #[getter]
+    fn tau1(&self) -> f64 {
+        self.curve.params[4]
+    }
+
+    #[getter]
+    fn tau2(&self) -> f64 {
+        self.curve.params[5]
+    }
+});
@@ -0,0 +1,470 @@
+/*
+Yield curve built on the interpolation splines
+- Pillar rates are stored as continuously compounded zero rates
+- Interpolation runs on zero rates, log discount factors or discount factors
+- Zero rates are held flat before the first and after the last pillar
+- Dates are converted to times from the reference date with the curve day count,
+  act/act by calendar year as there is no coupon period
+- An optional piecewise linear zero spread is laid over the interpolated curve
+*/
+
+use chrono::NaiveDate;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::dates::day_count::DayCount;
+use crate::interpolate::cubic_spline::CubicSpline;
+use crate::interpolate::linear_spline::LinearSpline;
+use crate::rates::compounding::Compounding;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveVariable {
+    ZeroRate,
+    LogDiscount,
+    Discount,
+}
+
+impl CurveVariable {
+    pub fn parse(variable: &str) -> PyResult<Self> {
+        match variable {
+            "zero_rate" => Ok(CurveVariable::ZeroRate),
+            "log_discount" => Ok(CurveVariable::LogDiscount),
+            "discount" => Ok(CurveVariable::Discount),
+            _ => Err(PyValueError::new_err(
+                "Invalid interpolation variable. Use zero_rate, log_discount or discount",
+            )),
+        }
+    }
+
+    // Node value from a continuously compounded zero rate
+    fn node_value(&self, rate: f64, t: f64) -> f64 {
+        match self {
+            CurveVariable::ZeroRate => rate,
+            CurveVariable::LogDiscount => rate * t,
+            CurveVariable::Discount => (-rate * t).exp(),
+        }
+    }
+}
+
+
+#[derive(Clone)]
+enum Interpolator {
+    Linear(LinearSpline),
+    Cubic(CubicSpline),
+}
+
+impl Interpolator {
+    fn new(method: &str, x: Vec<f64>, y: Vec<f64>) -> PyResult<Self> {
+        match method {
+            "linear" => Ok(Interpolator::Linear(LinearSpline::new(x, y)?)),
+            "cubic" => Ok(Interpolator::Cubic(CubicSpline::new(x, y))),
+            _ => Err(PyValueError::new_err(
+                "Invalid interpolation method. Use linear or cubic",
+            )),
+        }
+    }
+
+    fn value(&self, x: f64) -> f64 {
+        match self {
+            Interpolator::Linear(spline) => spline.value(x),
+            Interpolator::Cubic(spline) => spline.value(x),
+        }
+        .unwrap()
+    }
+
+    fn derivative(&self, x: f64) -> f64 {
+        match self {
+            Interpolator::Linear(spline) => spline.derivative(x),
+            Interpolator::Cubic(spline) => spline.derivative(x),
+        }
+        .unwrap()
+    }
+}
+
+
+// Curve times can be given as year fractions or as dates
+#[derive(FromPyObject)]
+pub enum CurveTime {
+    Date(NaiveDate),
+    Time(f64),
+}
+
+
+#[derive(Clone)]
+#[pyclass]
+pub struct YieldCurve {
+    #[pyo3(get)]
+    times: Vec<f64>,
+    #[pyo3(get)]
+    zero_rates: Vec<f64>,
+    #[pyo3(get)]
+    interpolation: String,
+    #[pyo3(get)]
+    variable: String,
+    #[pyo3(get)]
+    reference_date: Option<NaiveDate>,
+    #[pyo3(get)]
+    day_count: String,
+    basis: DayCount,
+    curve_variable: CurveVariable,
+    interpolator: Interpolator,
+    spread: Option<LinearSpline>,
+}
+
+#[pymethods]
+impl YieldCurve {
+    #[new]
+    #[pyo3(signature = (
+        times,
+        rates,
+        compounding=None,
+        interpolation="linear",
+        variable="zero_rate",
+        reference_date=None,
+        day_count="act/365",
+    ))]
+    pub fn new(
+        times: Vec<f64>,
+        rates: Vec<f64>,
+        compounding: Option<Compounding>,
+        interpolation: &str,
+        variable: &str,
+        reference_date: Option<NaiveDate>,
+        day_count: &str,
+    ) -> Result<Self, PyErr> {
+        if times.len() != rates.len() {
+            return Err(PyValueError::new_err(
+                "Vectors times and rates must have the same length",
+            ));
+        }
+        if times.is_empty() || times[0] <= 0.0 || times.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(PyValueError::new_err(
+                "Curve times must be positive and strictly increasing",
+            ));
+        }
+
+        // Convert the pillar rates to continuous compounding
+        let compounding = compounding.unwrap_or_else(Compounding::continuous);
+        let continuous = Compounding::continuous();
+        let zero_rates = times
+            .iter()
+            .zip(rates.iter())
+            .map(|(t, r)| compounding.convert(*r, *t, &continuous))
+            .collect::<PyResult<Vec<f64>>>()?;
+
+        YieldCurve::from_zero_rates(
+            times,
+            zero_rates,
+            interpolation,
+            variable,
+            reference_date,
+            day_count,
+        )
+    }
+
+    // Build a curve from discount factors at the pillar times
+    #[staticmethod]
+    #[pyo3(signature = (
+        times,
+        discount_factors,
+        interpolation="linear",
+        variable="zero_rate",
+        reference_date=None,
+        day_count="act/365",
+    ))]
+    pub fn from_discount_factors(
+        times: Vec<f64>,
+        discount_factors: Vec<f64>,
+        interpolation: &str,
+        variable: &str,
+        reference_date: Option<NaiveDate>,
+        day_count: &str,
+    ) -> Result<Self, PyErr> {
+        if discount_factors.iter().any(|df| *df <= 0.0) {
+            return Err(PyValueError::new_err("Discount factors must be positive"));
+        }
+
+        let rates: Vec<f64> = times
+            .iter()
+            .zip(discount_factors.iter())
+            .map(|(t, df)| -df.ln() / t)
+            .collect();
+
+        YieldCurve::new(
+            times,
+            rates,
+            None,
+            interpolation,
+            variable,
+            reference_date,
+            day_count,
+        )
+    }
+
+    pub fn discount_factor(&self, t: CurveTime) -> PyResult<f64> {
+        Ok(self.discount(self.to_time(t)?))
+    }
+
+    // Zero rate to time t, continuously compounded unless given
+    #[pyo3(signature = (t, compounding=None))]
+    pub fn zero_rate(&self, t: CurveTime, compounding: Option<Compounding>) -> PyResult<f64> {
+        let t = self.to_time(t)?;
+        match compounding {
+            Some(compounding) => compounding.implied_rate(self.discount(t), t),
+            None => Ok(self.zero(t)),
+        }
+    }
+
+    // Forward rate between t1 and t2, continuously compounded unless given
+    #[pyo3(signature = (t1, t2, compounding=None))]
+    pub fn forward_rate(
+        &self,
+        t1: CurveTime,
+        t2: CurveTime,
+        compounding: Option<Compounding>,
+    ) -> PyResult<f64> {
+        let (t1, t2) = (self.to_time(t1)?, self.to_time(t2)?);
+        if t2 <= t1 {
+            return Err(PyValueError::new_err("Forward end must be after the start"));
+        }
+
+        let compounding = compounding.unwrap_or_else(Compounding::continuous);
+        compounding.implied_rate(self.discount(t2) / self.discount(t1), t2 - t1)
+    }
+
+    pub fn instantaneous_forward(&self, t: CurveTime) -> PyResult<f64> {
+        Ok(self.forward(self.to_time(t)?))
+    }
+
+    // One period forward rates on a grid of n steps of dt years, for the rate trees
+    #[pyo3(signature = (n_steps, dt=1.0, compounding=None))]
+    pub fn forward_curve(
+        &self,
+        n_steps: usize,
+        dt: f64,
+        compounding: Option<Compounding>,
+    ) -> PyResult<Vec<f64>> {
+        if dt <= 0.0 {
+            return Err(PyValueError::new_err("Time step must be positive"));
+        }
+
+        let compounding = compounding.unwrap_or_else(Compounding::continuous);
+        (0..n_steps)
+            .map(|n| {
+                let (t1, t2) = (n as f64 * dt, (n + 1) as f64 * dt);
+                compounding.implied_rate(self.discount(t2) / self.discount(t1), dt)
+            })
+            .collect()
+    }
+
+    // Copy of the curve with every zero rate shifted in parallel
+    pub fn shifted(&self, shift: f64) -> PyResult<YieldCurve> {
+        let zero_rates = self.zero_rates.iter().map(|r| r + shift).collect();
+        self.with_zero_rates(zero_rates)
+    }
+
+    // Copy of the curve with a zero spread, linear between the times and flat outside them
+    pub fn with_spread(&self, times: Vec<f64>, spreads: Vec<f64>) -> PyResult<YieldCurve> {
+        if times.len() != spreads.len() {
+            return Err(PyValueError::new_err(
+                "Vectors times and spreads must have the same length",
+            ));
+        }
+        if times.is_empty() || times.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(PyValueError::new_err(
+                "Spread times must be strictly increasing",
+            ));
+        }
+
+        let (mut x, mut y) = (times, spreads);
+        if x.len() == 1 {
+            x.push(x[0] + 1.0);
+            y.push(y[0]);
+        }
+        let mut curve = self.clone();
+        curve.spread = Some(LinearSpline::new(x, y)?);
+
+        Ok(curve)
+    }
+
+    // Triangular bump at one key time, rising from the previous key and falling to the next
+    // The first and last bumps run flat to the ends so the bumps add up to a parallel shift
+    pub fn key_rate_bumped(&self, key_times: Vec<f64>, index: usize, bump: f64) -> PyResult<YieldCurve> {
+        if index >= key_times.len() {
+            return Err(PyValueError::new_err("Key rate index out of range"));
+        }
+
+        let spreads = (0..key_times.len())
+            .map(|k| if k == index { bump } else { 0.0 })
+            .collect();
+        self.with_spread(key_times, spreads)
+    }
+}
+
+impl YieldCurve {
+    pub fn from_zero_rates(
+        times: Vec<f64>,
+        zero_rates: Vec<f64>,
+        interpolation: &str,
+        variable: &str,
+        reference_date: Option<NaiveDate>,
+        day_count: &str,
+    ) -> Result<Self, PyErr> {
+        let curve_variable = CurveVariable::parse(variable)?;
+        let basis = DayCount::parse(day_count)?;
+
+        // Log discount and discount interpolation are anchored at t = 0
+        let mut x = Vec::new();
+        let mut y = Vec::new();
+        if curve_variable != CurveVariable::ZeroRate {
+            x.push(0.0);
+            y.push(curve_variable.node_value(0.0, 0.0));
+        }
+        for (t, r) in times.iter().zip(zero_rates.iter()) {
+            x.push(*t);
+            y.push(curve_variable.node_value(*r, *t));
+        }
+
+        // A single pillar is a flat curve
+        if x.len() == 1 {
+            x.push(x[0] + 1.0);
+            y.push(y[0]);
+        }
+
+        Ok(YieldCurve {
+            times,
+            zero_rates,
+            interpolation: interpolation.to_string(),
+            variable: variable.to_string(),
+            reference_date,
+            day_count: day_count.to_string(),
+            basis,
+            curve_variable,
+            interpolator: Interpolator::new(interpolation, x, y)?,
+            spread: None,
+        })
+    }
+
+    // Same curve settings and spread on new pillar zero rates
+    pub fn with_zero_rates(&self, zero_rates: Vec<f64>) -> PyResult<YieldCurve> {
+        let mut curve = YieldCurve::from_zero_rates(
+            self.times.clone(),
+            zero_rates,
+            &self.interpolation,
+            &self.variable,
+            self.reference_date,
+            &self.day_count,
+        )?;
+        curve.spread = self.spread.clone();
+
+        Ok(curve)
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn zero_rates(&self) -> &[f64] {
+        &self.zero_rates
+    }
+
+    pub fn reference_date(&self) -> Option<NaiveDate> {
+        self.reference_date
+    }
+
+    pub fn to_time(&self, t: CurveTime) -> PyResult<f64> {
+        match t {
+            CurveTime::Time(t) => Ok(t),
+            CurveTime::Date(date) => self.date_to_time(date),
+        }
+    }
+
+    pub fn date_to_time(&self, date: NaiveDate) -> PyResult<f64> {
+        match self.reference_date {
+            Some(reference) => Ok(self.basis.year_time(reference, date)),
+            None => Err(PyValueError::new_err(
+                "Curve needs a reference date to evaluate dates",
+            )),
+        }
+    }
+
+    fn first_time(&self) -> f64 {
+        self.times[0]
+    }
+
+    fn last_time(&self) -> f64 {
+        self.times[self.times.len() - 1]
+    }
+
+    // Continuously compounded zero rate
+    pub fn zero(&self, t: f64) -> f64 {
+        let (spread, _) = self.spread_at(t);
+        self.curve_zero(t) + spread
+    }
+
+    pub fn discount(&self, t: f64) -> f64 {
+        if t <= 0.0 {
+            return 1.0;
+        }
+        (-self.zero(t) * t).exp()
+    }
+
+    // Instantaneous forward rate, the derivative of r(t) * t
+    pub fn forward(&self, t: f64) -> f64 {
+        let (spread, slope) = self.spread_at(t);
+        self.curve_forward(t) + spread + t.max(0.0) * slope
+    }
+
+    // Spread and its slope, held flat outside the spread times
+    fn spread_at(&self, t: f64) -> (f64, f64) {
+        match &self.spread {
+            Some(spread) => {
+                let (first, last) = spread.range();
+                let value = spread.value(t.clamp(first, last)).unwrap();
+                if t < first || t > last {
+                    (value, 0.0)
+                } else {
+                    (value, spread.derivative(t).unwrap())
+                }
+            }
+            None => (0.0, 0.0),
+        }
+    }
+
+    fn curve_zero(&self, t: f64) -> f64 {
+        if t > self.last_time() {
+            return self.zero_rates[self.zero_rates.len() - 1];
+        }
+        if t < self.first_time() && self.curve_variable == CurveVariable::ZeroRate {
+            return self.zero_rates[0];
+        }
+        if t <= 0.0 {
+            return self.curve_forward(0.0);
+        }
+
+        let value = self.interpolator.value(t);
+        match self.curve_variable {
+            CurveVariable::ZeroRate => value,
+            CurveVariable::LogDiscount => value / t,
+            CurveVariable::Discount => -value.ln() / t,
+        }
+    }
+
+    fn curve_forward(&self, t: f64) -> f64 {
+        if t > self.last_time() {
+            return self.zero_rates[self.zero_rates.len() - 1];
+        }
+        if t < self.first_time() && self.curve_variable == CurveVariable::ZeroRate {
+            return self.zero_rates[0];
+        }
+
+        let t = t.max(0.0);
+        let slope = self.interpolator.derivative(t);
+        match self.curve_variable {
+            CurveVariable::ZeroRate => self.interpolator.value(t) + t * slope,
+            CurveVariable::LogDiscount => slope,
+            CurveVariable::Discount => -slope / self.interpolator.value(t),
+        }
+    }
+}
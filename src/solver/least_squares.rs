@@ -0,0 +1,140 @@
+/*
+Bounded nonlinear least squares with Levenberg-Marquardt
+- Jacobian by forward differences, stepping backwards at an upper bound
+- Bounds are enforced by projecting each step back into the box
+- Parameters pinned at a bound by the gradient are held fixed for the step
+*/
+
+use nalgebra::{DMatrix, DVector};
+
+
+pub struct LeastSquaresFit {
+    pub params: Vec<f64>,
+    pub residuals: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+
+// Minimise the sum of squared residuals over params within [lower, upper]
+pub fn levenberg_marquardt<F>(
+    residuals: F,
+    initial: &[f64],
+    lower: &[f64],
+    upper: &[f64],
+    tol: f64,
+    max_iter: usize,
+) -> LeastSquaresFit
+where
+    F: Fn(&[f64]) -> Vec<f64>,
+{
+    let n = initial.len();
+    let project = |x: &mut DVector<f64>| {
+        for k in 0..n {
+            x[k] = x[k].clamp(lower[k], upper[k]);
+        }
+    };
+
+    let mut x = DVector::from_column_slice(initial);
+    project(&mut x);
+    let mut r = DVector::from_vec(residuals(x.as_slice()));
+    let mut cost = r.norm_squared();
+    let mut lambda = 1e-3;
+    let mut converged = false;
+    let mut iterations = 0;
+
+    while iterations < max_iter {
+        iterations += 1;
+
+        // Forward difference jacobian
+        let mut jacobian = DMatrix::zeros(r.len(), n);
+        for k in 0..n {
+            let mut h = 1e-7 * x[k].abs().max(1.0);
+            if x[k] + h > upper[k] {
+                h = -h;
+            }
+            let mut x_h = x.clone();
+            x_h[k] += h;
+            let r_h = DVector::from_vec(residuals(x_h.as_slice()));
+            jacobian.set_column(k, &((r_h - &r) / h));
+        }
+
+        let jtj = jacobian.transpose() * &jacobian;
+        let mut gradient = jacobian.transpose() * &r;
+
+        // Parameters held at a bound by the gradient are fixed for this step
+        let active: Vec<bool> = (0..n)
+            .map(|k| {
+                (x[k] <= lower[k] && gradient[k] > 0.0) || (x[k] >= upper[k] && gradient[k] < 0.0)
+            })
+            .collect();
+        for k in 0..n {
+            if active[k] {
+                gradient[k] = 0.0;
+            }
+        }
+        if gradient.amax() < tol {
+            converged = true;
+            break;
+        }
+
+        // Increase damping until the step reduces the cost
+        loop {
+            let mut damped = jtj.clone();
+            for k in 0..n {
+                damped[(k, k)] += lambda * jtj[(k, k)].max(1e-12);
+                if active[k] {
+                    damped.row_mut(k).fill(0.0);
+                    damped.column_mut(k).fill(0.0);
+                    damped[(k, k)] = 1.0;
+                }
+            }
+            let step = match damped.lu().solve(&(-&gradient)) {
+                Some(step) => step,
+                None => {
+                    lambda *= 10.0;
+                    if lambda > 1e12 {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            let mut x_new = &x + &step;
+            project(&mut x_new);
+            let r_new = DVector::from_vec(residuals(x_new.as_slice()));
+            let cost_new = r_new.norm_squared();
+
+            if cost_new.is_finite() && cost_new < cost {
+                let improvement = cost - cost_new;
+                let moved = (&x_new - &x).norm();
+                x = x_new;
+                r = r_new;
+                cost = cost_new;
+                lambda = (lambda / 10.0).max(1e-12);
+                if improvement < tol * (1.0 + cost) || moved < tol * (1.0 + x.norm()) {
+                    converged = true;
+                }
+                break;
+            }
+
+            lambda *= 10.0;
+            if lambda > 1e12 {
+                // No descent step left, the current point is a (bounded) minimum
+                converged = true;
+                break;
+            }
+        }
+
+        if converged {
+            break;
+        }
+    }
+
+    LeastSquaresFit {
+        params: x.as_slice().to_vec(),
+        residuals: r.as_slice().to_vec(),
+        iterations,
+        converged,
+    }
+}
@@ -0,0 +1,5 @@
+// Numerical Solvers
+// Rust only helpers shared by the pricers, not exposed to python
+
+pub mod least_squares;
+pub mod root;
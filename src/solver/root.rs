@@ -0,0 +1,150 @@
+/*
+One dimensional root finders
+- Newton iteration from an initial guess
+- Brent's method on a bracketing interval, used as the fallback when Newton fails
+*/
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+
+
+// Newton iteration, None if it leaves the bracket, hits a flat slope or does not converge
+pub fn newton<F, D>(f: F, df: D, x0: f64, bounds: (f64, f64), tol: f64, max_iter: usize) -> Option<f64>
+where
+    F: Fn(f64) -> f64,
+    D: Fn(f64) -> f64,
+{
+    let mut x = x0;
+    for _ in 0..max_iter {
+        let fx = f(x);
+        if fx.abs() < tol {
+            return Some(x);
+        }
+
+        let dfx = df(x);
+        if dfx == 0.0 || !dfx.is_finite() {
+            return None;
+        }
+
+        let step = fx / dfx;
+        x -= step;
+        if !x.is_finite() || x <= bounds.0 || x >= bounds.1 {
+            return None;
+        }
+        if step.abs() < tol {
+            return Some(x);
+        }
+    }
+
+    None
+}
+
+// Widen an interval from a starting guess until the function changes sign
+pub fn bracket<F>(f: F, lower: f64, upper: f64, bounds: (f64, f64)) -> PyResult<(f64, f64)>
+where
+    F: Fn(f64) -> f64,
+{
+    let (mut a, mut b) = (lower.max(bounds.0), upper.min(bounds.1));
+    for _ in 0..60 {
+        if f(a) * f(b) <= 0.0 {
+            return Ok((a, b));
+        }
+        let width = b - a;
+        a = (a - width).max(bounds.0);
+        b = (b + width).min(bounds.1);
+    }
+
+    Err(PyRuntimeError::new_err(
+        "Unable to bracket a root, check the inputs are attainable",
+    ))
+}
+
+// Brent's method on [a, b], f(a) and f(b) must have opposite signs
+pub fn brent<F>(f: F, a: f64, b: f64, tol: f64, max_iter: usize) -> PyResult<f64>
+where
+    F: Fn(f64) -> f64,
+{
+    let (mut a, mut b) = (a, b);
+    let (mut fa, mut fb) = (f(a), f(b));
+    if fa * fb > 0.0 {
+        return Err(PyRuntimeError::new_err("Root is not bracketed by the interval"));
+    }
+    if fa.abs() < fb.abs() {
+        std::mem::swap(&mut a, &mut b);
+        std::mem::swap(&mut fa, &mut fb);
+    }
+
+    let (mut c, mut fc) = (a, fa);
+    let mut d = b - a;
+    let mut bisected = true;
+    for _ in 0..max_iter {
+        if fb == 0.0 || (b - a).abs() < tol {
+            return Ok(b);
+        }
+
+        // Inverse quadratic interpolation or secant step
+        let mut s = if fa != fc && fb != fc {
+            a * fb * fc / ((fa - fb) * (fa - fc))
+                + b * fa * fc / ((fb - fa) * (fb - fc))
+                + c * fa * fb / ((fc - fa) * (fc - fb))
+        } else {
+            b - fb * (b - a) / (fb - fa)
+        };
+
+        // Fall back to bisection when the step is not acceptable
+        let mid = (3.0 * a + b) / 4.0;
+        if (s - mid) * (s - b) >= 0.0
+            || (bisected && (s - b).abs() >= (b - c).abs() / 2.0)
+            || (!bisected && (s - b).abs() >= (c - d).abs() / 2.0)
+            || (bisected && (b - c).abs() < tol)
+            || (!bisected && (c - d).abs() < tol)
+        {
+            s = (a + b) / 2.0;
+            bisected = true;
+        } else {
+            bisected = false;
+        }
+
+        let fs = f(s);
+        d = c;
+        c = b;
+        fc = fb;
+        if fa * fs < 0.0 {
+            b = s;
+            fb = fs;
+        } else {
+            a = s;
+            fa = fs;
+        }
+        if fa.abs() < fb.abs() {
+            std::mem::swap(&mut a, &mut b);
+            std::mem::swap(&mut fa, &mut fb);
+        }
+    }
+
+    Err(PyRuntimeError::new_err(format!(
+        "Brent solver did not converge after {} iterations",
+        max_iter
+    )))
+}
+
+// Newton from x0 with a bracketed Brent fallback, roots are searched inside bounds
+pub fn solve<F, D>(
+    f: F,
+    df: D,
+    x0: f64,
+    bounds: (f64, f64),
+    tol: f64,
+    max_iter: usize,
+) -> PyResult<f64>
+where
+    F: Fn(f64) -> f64,
+    D: Fn(f64) -> f64,
+{
+    if let Some(x) = newton(&f, &df, x0, bounds, tol, max_iter) {
+        return Ok(x);
+    }
+
+    let (a, b) = bracket(&f, x0 - 0.05, x0 + 0.05, bounds)?;
+    brent(&f, a, b, tol, max_iter)
+}